@@ -0,0 +1,118 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Embed the metadata shown by `--version` that Cargo does not provide itself: the commit being
+/// built, the date of the build, and the version of handlebars the build is locked to.
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let manifest_dir = Path::new(&manifest_dir);
+
+    for path in git_watched(manifest_dir) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let commit = git_commit(manifest_dir).unwrap_or_else(|| "unknown".to_owned());
+    let date = build_date();
+    let handlebars = locked_version(&manifest_dir.join("Cargo.lock"), "handlebars")
+        .unwrap_or_else(|| "unknown".to_owned());
+
+    println!("cargo:rustc-env=HANDLEBARS_CLI_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=HANDLEBARS_CLI_BUILD_DATE={}", date);
+    println!(
+        "cargo:rustc-env=HANDLEBARS_CLI_HANDLEBARS_VERSION={}",
+        handlebars
+    );
+}
+
+/// The short hash of the commit being built, if it is being built from a git checkout.
+fn git_commit(dir: &Path) -> Option<String> {
+    git(dir, &["rev-parse", "--short", "HEAD"])
+}
+
+/// The files which change when a commit is made or checked out, so that the build script runs
+/// again to embed the new commit: HEAD, the branch it refers to, and the packed refs that branch
+/// may be kept in instead. Only those which are there are watched, since Cargo would otherwise
+/// run the build script on every build.
+fn git_watched(dir: &Path) -> Vec<PathBuf> {
+    let (git_dir, common_dir) = match (
+        git(dir, &["rev-parse", "--git-dir"]),
+        git(dir, &["rev-parse", "--git-common-dir"]),
+    ) {
+        (Some(git_dir), Some(common_dir)) => (dir.join(git_dir), dir.join(common_dir)),
+        _ => return Vec::new(),
+    };
+    let head = git_dir.join("HEAD");
+    let branch = fs::read_to_string(&head)
+        .ok()
+        .and_then(|head| Some(common_dir.join(head.strip_prefix("ref:")?.trim())));
+
+    vec![Some(head), branch, Some(common_dir.join("packed-refs"))]
+        .into_iter()
+        .flatten()
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// The trimmed output of git run with the arguments in a directory, if it succeeds with any.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8(output.stdout).ok()?.trim().to_owned();
+    Some(output).filter(|output| !output.is_empty())
+}
+
+/// The date of the build as YYYY-MM-DD in UTC, taken from `SOURCE_DATE_EPOCH` when it is set so
+/// that reproducible builds stay reproducible.
+fn build_date() -> String {
+    let seconds = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default()
+        });
+
+    // Convert days since the epoch to a civil date, as described by Howard Hinnant.
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The version of a package in the lockfile, if there is a lockfile.
+fn locked_version(lockfile: &Path, package: &str) -> Option<String> {
+    let lockfile = fs::read_to_string(lockfile).ok()?;
+    let name = format!("name = \"{}\"", package);
+    let mut lines = lockfile.lines();
+    lines.find(|line| *line == name)?;
+    let version = lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')?;
+    Some(version.to_owned())
+}
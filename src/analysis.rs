@@ -0,0 +1,417 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::mem;
+
+use handlebars::template::{
+    BlockParam, DecoratorTemplate, HelperTemplate, Parameter, Template, TemplateElement,
+};
+use handlebars::Handlebars;
+use serde_json::{json, Map, Value as Json};
+
+/// What the properties are expected to hold at a path, judged from how the templates use it.
+#[derive(Debug, Default)]
+pub struct Shape {
+    /// Whether the value is used directly, such as by being rendered, given to a helper or used as
+    /// the context of a block.
+    pub used: bool,
+    /// Whether the value is tested by `#if` or `#unless`.
+    pub tested: bool,
+    /// What each element is expected to hold, if the value is iterated over by `#each`.
+    pub items: Option<Box<Shape>>,
+    /// What the fields are expected to hold, by name.
+    pub fields: BTreeMap<String, Shape>,
+}
+
+impl Shape {
+    /// The shape of the properties the template with the given name uses, along with any partials
+    /// it includes from the registry. Names used without arguments which are in `helpers` are
+    /// taken to be helpers rather than properties.
+    pub fn of(handlebars: &Handlebars, name: &str, helpers: &BTreeSet<String>) -> Shape {
+        let mut walker = Walker {
+            handlebars,
+            helpers,
+            shape: Shape::default(),
+            partials: vec![name.to_owned()],
+        };
+        if let Some(template) = handlebars.get_template(name) {
+            walker.template(template, &Scope::default());
+        }
+        walker.shape
+    }
+
+    /// Every path the templates use, sorted, with the fields of an object after a dot and the
+    /// elements of an array as `[]`, such as `items[].name`.
+    pub fn paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.collect_paths(String::new(), &mut paths);
+        paths
+    }
+
+    fn collect_paths(&self, path: String, paths: &mut Vec<String>) {
+        let referenced = self.used || self.tested || self.items.is_some();
+        if referenced && !path.is_empty() {
+            paths.push(path.clone());
+        }
+        if let Some(items) = &self.items {
+            items.collect_paths(format!("{}[]", path), paths);
+        }
+        for (name, field) in &self.fields {
+            let path = match path.as_str() {
+                "" => name.clone(),
+                path => format!("{}.{}", path, name),
+            };
+            field.collect_paths(path, paths);
+        }
+    }
+
+    /// A JSON Schema describing the properties, as best as can be told: an array for a value
+    /// iterated over, an object for one with fields, a boolean for one which is only tested, and
+    /// any other value which can be rendered otherwise.
+    ///
+    /// Every field except those which are only tested is required.
+    pub fn schema(&self) -> Json {
+        let mut schema = self.schema_of();
+        if let Json::Object(schema) = &mut schema {
+            schema.insert(
+                "$schema".to_owned(),
+                "https://json-schema.org/draft/2020-12/schema".into(),
+            );
+        }
+        schema
+    }
+
+    fn schema_of(&self) -> Json {
+        if let Some(items) = &self.items {
+            json!({ "type": "array", "items": items.schema_of() })
+        } else if !self.fields.is_empty() {
+            let properties = self
+                .fields
+                .iter()
+                .map(|(name, field)| (name.clone(), field.schema_of()))
+                .collect::<Map<_, _>>();
+            let required = self
+                .fields
+                .iter()
+                .filter(|(_, field)| field.required())
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>();
+            json!({ "type": "object", "properties": properties, "required": required })
+        } else if self.tested && !self.used {
+            json!({ "type": "boolean" })
+        } else {
+            json!({ "type": ["string", "number", "boolean"] })
+        }
+    }
+
+    /// Properties with a placeholder for every value: an array of one example element for a value
+    /// iterated over, an object for one with fields, `true` for one which is only tested so that
+    /// the blocks it guards render, and `"<string>"` otherwise.
+    pub fn example(&self) -> Json {
+        if let Some(items) = self.items.as_ref().filter(|items| !items.is_unknown()) {
+            json!([items.example()])
+        } else if self.items.is_some() {
+            json!([])
+        } else if !self.fields.is_empty() {
+            let fields = self
+                .fields
+                .iter()
+                .map(|(name, field)| (name.clone(), field.example()))
+                .collect::<Map<_, _>>();
+            Json::Object(fields)
+        } else if self.tested && !self.used {
+            Json::Bool(true)
+        } else if self.used {
+            json!("<string>")
+        } else {
+            json!({})
+        }
+    }
+
+    /// Whether nothing is known of how the value is used.
+    fn is_unknown(&self) -> bool {
+        !self.used && !self.tested && self.items.is_none() && self.fields.is_empty()
+    }
+
+    /// Whether the value has to be there for the templates to render, which is the case unless it
+    /// is only tested.
+    fn required(&self) -> bool {
+        self.used || self.items.is_some() || self.fields.values().any(Shape::required)
+    }
+
+    /// The shape at the path below this one, which is added if it is not there yet.
+    fn at(&mut self, path: &[Segment]) -> &mut Shape {
+        path.iter().fold(self, |shape, segment| match segment {
+            Segment::Field(name) => shape.fields.entry(name.clone()).or_default(),
+            Segment::Items => shape.items.get_or_insert_with(Default::default),
+        })
+    }
+}
+
+/// An enumeration of the steps of a path from a value to one inside it.
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    /// The field of an object with this name.
+    Field(String),
+    /// Any element of an array.
+    Items,
+}
+
+/// Where the paths used in a block lead from: the contexts of the blocks it is nested in, with the
+/// innermost last, and the block parameters by name.
+#[derive(Clone, Default)]
+struct Scope {
+    contexts: Vec<Vec<Segment>>,
+    params: HashMap<String, Vec<Segment>>,
+}
+
+impl Scope {
+    /// The scope of a block with the given path as its context.
+    fn with_context(&self, context: Vec<Segment>) -> Scope {
+        let mut scope = self.clone();
+        scope.contexts.push(context);
+        scope
+    }
+
+    /// The path from the top of the properties a path used in a template leads to, or nothing if
+    /// it is to a local variable such as `@index` instead.
+    fn resolve(&self, raw: &str) -> Option<Vec<Segment>> {
+        let mut rest = raw;
+        let mut up = 0;
+        loop {
+            if let Some(after) = rest.strip_prefix("../") {
+                up += 1;
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("./") {
+                rest = after;
+            } else {
+                break;
+            }
+        }
+
+        let mut tokens = tokens(rest);
+        let mut path = match tokens.first().map(String::as_str) {
+            Some("@root") => {
+                tokens.remove(0);
+                Vec::new()
+            }
+            Some(token) if token.starts_with('@') => return None,
+            Some("this") => {
+                tokens.remove(0);
+                self.context(up)
+            }
+            Some(token) if up == 0 && self.params.contains_key(token) => {
+                self.params[&tokens.remove(0)].clone()
+            }
+            _ => self.context(up),
+        };
+        path.extend(tokens.into_iter().map(|token| {
+            if token.bytes().all(|b| b.is_ascii_digit()) {
+                Segment::Items
+            } else {
+                Segment::Field(token)
+            }
+        }));
+        Some(path)
+    }
+
+    /// The context the given number of blocks out from the innermost one.
+    fn context(&self, up: usize) -> Vec<Segment> {
+        self.contexts
+            .len()
+            .checked_sub(up + 1)
+            .map(|index| self.contexts[index].clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Split a path used in a template into its names, such as `a.[b c]/d` into `a`, `b c` and `d`.
+fn tokens(raw: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => token.extend(chars.by_ref().take_while(|c| *c != ']')),
+            '.' | '/' => tokens.push(mem::take(&mut token)),
+            c => token.push(c),
+        }
+    }
+    tokens.push(token);
+    tokens.retain(|token| !token.is_empty());
+    tokens
+}
+
+/// Walks through templates, recording the paths they use into the shape of the properties.
+struct Walker<'a> {
+    handlebars: &'a Handlebars<'a>,
+    helpers: &'a BTreeSet<String>,
+    shape: Shape,
+    /// The partials being walked through, so that those including themselves end.
+    partials: Vec<String>,
+}
+
+impl Walker<'_> {
+    fn template(&mut self, template: &Template, scope: &Scope) {
+        for element in &template.elements {
+            match element {
+                TemplateElement::Expression(helper) | TemplateElement::HtmlExpression(helper) => {
+                    self.expression(helper, scope)
+                }
+                TemplateElement::HelperBlock(helper) => self.block(helper, scope),
+                TemplateElement::PartialExpression(partial)
+                | TemplateElement::PartialBlock(partial) => self.partial(partial, scope),
+                TemplateElement::DecoratorExpression(decorator)
+                | TemplateElement::DecoratorBlock(decorator) => {
+                    self.arguments(&decorator.params, &decorator.hash, scope);
+                    if let Some(template) = &decorator.template {
+                        self.template(template, scope);
+                    }
+                }
+                TemplateElement::RawString(_) | TemplateElement::Comment(_) => {}
+            }
+        }
+    }
+
+    /// Record an expression such as `{{name}}`, or `{{helper name}}`.
+    fn expression(&mut self, helper: &HelperTemplate, scope: &Scope) {
+        let is_value = helper.params.is_empty()
+            && helper.hash.is_empty()
+            && matches!(helper.name, Parameter::Path(_))
+            && !helper
+                .name
+                .as_name()
+                .is_some_and(|name| self.helpers.contains(name));
+        if is_value {
+            self.parameter(&helper.name, scope);
+        } else {
+            self.arguments(&helper.params, &helper.hash, scope);
+        }
+    }
+
+    /// Record a block such as `{{#each items}}...{{/each}}`, walking through its contents with the
+    /// context the helper gives them.
+    fn block(&mut self, helper: &HelperTemplate, scope: &Scope) {
+        let first = helper.params.first();
+        let inner = match helper.name.as_name() {
+            Some("if") | Some("unless") => {
+                if let Some(path) = first.and_then(|param| self.path(param, scope)) {
+                    self.shape.at(&path).tested = true;
+                }
+                self.arguments(
+                    helper.params.get(1..).unwrap_or_default(),
+                    &helper.hash,
+                    scope,
+                );
+                scope.clone()
+            }
+            Some("each") => match first.and_then(|param| self.path(param, scope)) {
+                Some(mut path) => {
+                    self.shape
+                        .at(&path)
+                        .items
+                        .get_or_insert_with(Default::default);
+                    path.push(Segment::Items);
+                    self.block_scope(helper, scope, path)
+                }
+                None => scope.clone(),
+            },
+            Some("with") => match first.and_then(|param| self.path(param, scope)) {
+                Some(path) => {
+                    self.shape.at(&path).used = true;
+                    self.block_scope(helper, scope, path)
+                }
+                None => scope.clone(),
+            },
+            _ => {
+                self.arguments(&helper.params, &helper.hash, scope);
+                scope.clone()
+            }
+        };
+
+        if let Some(template) = &helper.template {
+            self.template(template, &inner);
+        }
+        if let Some(inverse) = &helper.inverse {
+            self.template(inverse, scope);
+        }
+    }
+
+    /// The scope of a block with the given context, where the first block parameter is the
+    /// context too, as with `{{#each items as |item|}}`.
+    fn block_scope(&self, helper: &HelperTemplate, scope: &Scope, context: Vec<Segment>) -> Scope {
+        let mut inner = scope.with_context(context.clone());
+        let param = match &helper.block_param {
+            Some(BlockParam::Single(param)) | Some(BlockParam::Pair((param, _))) => param,
+            None => return inner,
+        };
+        if let Some(name) = param.as_name() {
+            inner.params.insert(name.to_owned(), context);
+        }
+        inner
+    }
+
+    /// Record a partial such as `{{> header page}}`, walking through the partial from the registry
+    /// with its context and with its hash arguments as block parameters.
+    fn partial(&mut self, partial: &DecoratorTemplate, scope: &Scope) {
+        let mut inner = match partial.params.first() {
+            Some(param) => match self.path(param, scope) {
+                Some(path) => scope.with_context(path),
+                None => scope.clone(),
+            },
+            None => scope.clone(),
+        };
+        for (name, value) in &partial.hash {
+            if let Some(path) = self.path(value, scope) {
+                inner.params.insert(name.clone(), path);
+            }
+        }
+        if let Some(template) = &partial.template {
+            self.template(template, scope);
+        }
+
+        let name = match &partial.name {
+            Parameter::Name(name) => name,
+            _ => return,
+        };
+        if self.partials.contains(name) {
+            return;
+        }
+        if let Some(template) = self.handlebars.get_template(name) {
+            self.partials.push(name.clone());
+            self.template(template, &inner);
+            self.partials.pop();
+        }
+    }
+
+    fn arguments(
+        &mut self,
+        params: &[Parameter],
+        hash: &HashMap<String, Parameter>,
+        scope: &Scope,
+    ) {
+        for param in params.iter().chain(hash.values()) {
+            self.parameter(param, scope);
+        }
+    }
+
+    /// Record a value given to a helper, or rendered.
+    fn parameter(&mut self, param: &Parameter, scope: &Scope) {
+        if let Some(path) = self.path(param, scope) {
+            self.shape.at(&path).used = true;
+        }
+    }
+
+    /// The path a parameter leads to, if it is a path to a property. Subexpressions are recorded
+    /// along the way.
+    fn path(&mut self, param: &Parameter, scope: &Scope) -> Option<Vec<Segment>> {
+        match param {
+            Parameter::Path(_) => param.as_name().and_then(|raw| scope.resolve(raw)),
+            Parameter::Subexpression(subexpression) => {
+                if let TemplateElement::Expression(helper) = subexpression.as_element() {
+                    self.expression(helper, scope);
+                }
+                None
+            }
+            Parameter::Name(_) | Parameter::Literal(_) => None,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::io::{self, Cursor, Write};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::info;
+use snafu::ResultExt;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::output;
+use crate::{ArchiveFormatUnknownSnafu, ArchiveUnwritableSnafu, ArchiveZipUnwritableSnafu, Result};
+
+/// An enumeration of the kinds of archive the rendered files can be collected into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Guess the kind of archive from the extension of its path.
+    ///
+    /// It fails if the extension is not one of a known kind of archive.
+    fn from_path(path: &Path) -> Result<ArchiveFormat> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Ok(ArchiveFormat::Tar)
+        } else if name.ends_with(".zip") {
+            Ok(ArchiveFormat::Zip)
+        } else {
+            ArchiveFormatUnknownSnafu { path }.fail()
+        }
+    }
+}
+
+/// An enumeration of the kinds of entry in an archive.
+enum Entry {
+    /// A file with these contents.
+    File(Vec<u8>),
+    /// A symlink pointing to this target.
+    Link(PathBuf),
+}
+
+/// An archive given with `--archive` which the rendered files are collected into, in the order
+/// they were rendered, before it is written all at once.
+pub struct Archive {
+    path: PathBuf,
+    format: ArchiveFormat,
+    entries: Vec<(String, Entry)>,
+}
+
+impl Archive {
+    /// Create an empty archive to be written to the given path, of the kind matching its extension.
+    ///
+    /// It fails if the extension is not one of a known kind of archive.
+    pub fn new(path: &Path) -> Result<Archive> {
+        Ok(Archive {
+            path: path.to_owned(),
+            format: ArchiveFormat::from_path(path)?,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Add a file to the archive. Only the normal components of the path are kept, so that every
+    /// entry is relative and stays inside the archive.
+    pub fn add(&mut self, path: &Path, contents: &[u8]) {
+        self.entries
+            .push((entry_name(path), Entry::File(contents.to_owned())));
+    }
+
+    /// Add a symlink pointing to the given target to the archive, with its path kept as for a file.
+    pub fn add_link(&mut self, path: &Path, target: &Path) {
+        self.entries
+            .push((entry_name(path), Entry::Link(target.to_owned())));
+    }
+
+    /// Write the archive with all of the files added to it, in the same way as any other output.
+    ///
+    /// It fails if the archive could not be built.
+    /// It fails if the archive could not be written.
+    pub fn write(&self, mkdir: bool) -> Result<()> {
+        let path = &self.path;
+        let bytes = match self.format {
+            ArchiveFormat::Tar => self
+                .tar(Vec::new())
+                .context(ArchiveUnwritableSnafu { path })?,
+            ArchiveFormat::TarGz => self
+                .tar(GzEncoder::new(Vec::new(), Compression::default()))
+                .and_then(GzEncoder::finish)
+                .context(ArchiveUnwritableSnafu { path })?,
+            ArchiveFormat::Zip => self.zip().context(ArchiveZipUnwritableSnafu { path })?,
+        };
+        output::write_file(path, &bytes, mkdir)?;
+        info!(
+            "Wrote archive '{}' of {} files",
+            path.display(),
+            self.entries.len()
+        );
+        Ok(())
+    }
+
+    /// Build a tarball of the files into the given writer.
+    fn tar<W: Write>(&self, writer: W) -> io::Result<W> {
+        let mut builder = tar::Builder::new(writer);
+        for (name, entry) in &self.entries {
+            let mut header = tar::Header::new_gnu();
+            match entry {
+                Entry::File(contents) => {
+                    header.set_size(contents.len() as u64);
+                    header.set_mode(0o644);
+                    builder.append_data(&mut header, name, contents.as_slice())?;
+                }
+                Entry::Link(target) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_size(0);
+                    header.set_mode(0o777);
+                    builder.append_link(&mut header, name, target)?;
+                }
+            }
+        }
+        builder.into_inner()
+    }
+
+    /// Build a zip archive of the files.
+    fn zip(&self) -> zip::result::ZipResult<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().unix_permissions(0o644);
+        for (name, entry) in &self.entries {
+            match entry {
+                Entry::File(contents) => {
+                    writer.start_file(name.as_str(), options)?;
+                    writer.write_all(contents)?;
+                }
+                Entry::Link(target) => {
+                    let target = target.to_string_lossy();
+                    writer.add_symlink(name.as_str(), target, SimpleFileOptions::default())?;
+                }
+            }
+        }
+        Ok(writer.finish()?.into_inner())
+    }
+}
+
+/// The name of the entry for a path in an archive. Only the normal components of the path are
+/// kept, so that every entry is relative and stays inside the archive.
+fn entry_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
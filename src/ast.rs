@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use handlebars::template::{
+    BlockParam, DecoratorTemplate, HelperTemplate, Parameter, Template, TemplateElement,
+};
+use serde_json::{json, Map, Value as Json};
+
+/// The elements of the template as the engine parsed them, as an array of objects each with the
+/// type of the element, where it starts, what it is made of and the elements inside it.
+pub fn to_json(template: &Template) -> Json {
+    Json::Array(elements(template))
+}
+
+/// The elements of the template as the engine parsed them, one on each line, with the elements
+/// inside a block indented beneath it.
+pub fn to_text(template: &Template) -> String {
+    let mut text = String::new();
+    write_elements(&mut text, &elements(template), 0);
+    text
+}
+
+/// The nodes of the elements of a template, each with the line and column it starts at.
+fn elements(template: &Template) -> Vec<Json> {
+    template
+        .elements
+        .iter()
+        .enumerate()
+        .map(|(index, element)| {
+            let mut node = element_node(element);
+            if let (Some(mapping), Json::Object(node)) = (template.mapping.get(index), &mut node) {
+                node.insert("line".to_owned(), mapping.0.into());
+                node.insert("column".to_owned(), mapping.1.into());
+            }
+            node
+        })
+        .collect()
+}
+
+fn element_node(element: &TemplateElement) -> Json {
+    match element {
+        TemplateElement::RawString(text) => json!({ "type": "text", "text": text }),
+        TemplateElement::Comment(text) => json!({ "type": "comment", "text": text }),
+        TemplateElement::Expression(helper) => helper_node("expression", helper),
+        TemplateElement::HtmlExpression(helper) => helper_node("raw-expression", helper),
+        TemplateElement::HelperBlock(helper) => helper_node("block", helper),
+        TemplateElement::DecoratorExpression(decorator) => decorator_node("decorator", decorator),
+        TemplateElement::DecoratorBlock(decorator) => decorator_node("decorator-block", decorator),
+        TemplateElement::PartialExpression(partial) => decorator_node("partial", partial),
+        TemplateElement::PartialBlock(partial) => decorator_node("partial-block", partial),
+    }
+}
+
+fn helper_node(kind: &str, helper: &HelperTemplate) -> Json {
+    let mut node = call_node(kind, &helper.name, &helper.params, &helper.hash);
+    if let Some(block_param) = &helper.block_param {
+        let names = match block_param {
+            BlockParam::Single(param) => vec![parameter(param)],
+            BlockParam::Pair((first, second)) => vec![parameter(first), parameter(second)],
+        };
+        node.insert("block-params".to_owned(), names.into());
+    }
+    if let Some(template) = &helper.template {
+        node.insert("children".to_owned(), elements(template).into());
+    }
+    if let Some(inverse) = &helper.inverse {
+        node.insert("inverse".to_owned(), elements(inverse).into());
+    }
+    Json::Object(node)
+}
+
+fn decorator_node(kind: &str, decorator: &DecoratorTemplate) -> Json {
+    let mut node = call_node(kind, &decorator.name, &decorator.params, &decorator.hash);
+    if let Some(template) = &decorator.template {
+        node.insert("children".to_owned(), elements(template).into());
+    }
+    Json::Object(node)
+}
+
+/// The node of an element which calls a helper, decorator or partial with parameters.
+fn call_node(
+    kind: &str,
+    name: &Parameter,
+    params: &[Parameter],
+    hash: &HashMap<String, Parameter>,
+) -> Map<String, Json> {
+    let hash = hash
+        .iter()
+        .map(|(key, value)| (key.clone(), Json::from(parameter(value))))
+        .collect::<Map<_, _>>();
+    let mut node = Map::new();
+    node.insert("type".to_owned(), kind.into());
+    node.insert("name".to_owned(), parameter(name).into());
+    if !params.is_empty() {
+        let params = params.iter().map(parameter).collect::<Vec<_>>();
+        node.insert("params".to_owned(), params.into());
+    }
+    if !hash.is_empty() {
+        node.insert("hash".to_owned(), Json::Object(hash));
+    }
+    node
+}
+
+/// A parameter as it would be written in a template: a path or name as it is, a literal as JSON and
+/// a subexpression in parentheses.
+fn parameter(param: &Parameter) -> String {
+    match param {
+        Parameter::Name(_) | Parameter::Path(_) => param.as_name().unwrap_or_default().to_owned(),
+        Parameter::Literal(value) => value.to_string(),
+        Parameter::Subexpression(subexpression) => {
+            let mut text = format!("({}", subexpression.name());
+            for param in subexpression.params().into_iter().flatten() {
+                text.push(' ');
+                text.push_str(&parameter(param));
+            }
+            let mut hash = subexpression
+                .hash()
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            hash.sort_by_key(|(key, _)| *key);
+            for (key, value) in hash {
+                text.push_str(&format!(" {}={}", key, parameter(value)));
+            }
+            text.push(')');
+            text
+        }
+    }
+}
+
+/// Write the nodes on lines of their own at the given depth of indentation, followed by the
+/// nodes inside them indented further.
+fn write_elements(text: &mut String, nodes: &[Json], depth: usize) {
+    let indent = "  ".repeat(depth);
+    for node in nodes {
+        let field = |name: &str| node.get(name).and_then(Json::as_str).unwrap_or_default();
+        // The else of a chain such as {{else if}} is not mapped to where it starts.
+        let mut line = match (node.get("line"), node.get("column")) {
+            (Some(line), Some(column)) => vec![format!("{}:{}", line, column)],
+            _ => Vec::new(),
+        };
+        line.push(field("type").to_owned());
+        match field("type") {
+            "text" | "comment" => line.push(Json::from(field("text")).to_string()),
+            _ => {
+                line.push(field("name").to_owned());
+                let params = node.get("params").and_then(Json::as_array);
+                line.extend(params.into_iter().flatten().map(text_of));
+                let hash = node.get("hash").and_then(Json::as_object);
+                line.extend(
+                    hash.into_iter()
+                        .flatten()
+                        .map(|(key, value)| format!("{}={}", key, text_of(value))),
+                );
+                if let Some(names) = node.get("block-params").and_then(Json::as_array) {
+                    let names = names.iter().map(text_of).collect::<Vec<_>>();
+                    line.push(format!("as |{}|", names.join(" ")));
+                }
+            }
+        }
+        text.push_str(&format!("{}{}\n", indent, line.join(" ")));
+
+        if let Some(children) = node.get("children").and_then(Json::as_array) {
+            write_elements(text, children, depth + 1);
+        }
+        if let Some(inverse) = node.get("inverse").and_then(Json::as_array) {
+            text.push_str(&format!("{}else\n", indent));
+            write_elements(text, inverse, depth + 1);
+        }
+    }
+}
+
+/// The text of a string in a node.
+fn text_of(value: &Json) -> String {
+    value.as_str().unwrap_or_default().to_owned()
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use handlebars::{Handlebars, RenderError};
+use serde_json::Value as Json;
+
+/// How long rendering a template took over a number of renders.
+pub struct Timings {
+    renders: Vec<Duration>,
+    warmup: u64,
+}
+
+/// Render the template with the given name a number of times to warm up, and then a number of
+/// times more, timing each of those.
+///
+/// It fails if any render failed.
+pub fn bench(
+    handlebars: &Handlebars,
+    name: &str,
+    props: &Json,
+    warmup: u64,
+    iterations: u64,
+) -> Result<Timings, RenderError> {
+    for _ in 0..warmup {
+        handlebars.render(name, props)?;
+    }
+    let mut renders = Vec::new();
+    for _ in 0..iterations {
+        let start = Instant::now();
+        handlebars.render(name, props)?;
+        renders.push(start.elapsed());
+    }
+    renders.sort();
+    Ok(Timings { renders, warmup })
+}
+
+impl Timings {
+    fn mean(&self) -> Duration {
+        let total = self.renders.iter().sum::<Duration>();
+        Duration::from_secs_f64(total.as_secs_f64() / self.renders.len().max(1) as f64)
+    }
+
+    /// The time which the given percentage of renders took no longer than.
+    fn percentile(&self, percent: usize) -> Duration {
+        let index = (self.renders.len() * percent).div_ceil(100);
+        let index = index
+            .saturating_sub(1)
+            .min(self.renders.len().saturating_sub(1));
+        self.renders.get(index).copied().unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Rendered {} times after {} warm-up renders.",
+            self.renders.len(),
+            self.warmup
+        )?;
+        writeln!(f, "mean: {:?}", self.mean())?;
+        writeln!(f, "p50:  {:?}", self.percentile(50))?;
+        writeln!(f, "p95:  {:?}", self.percentile(95))?;
+        writeln!(f, "min:  {:?}", self.percentile(0))?;
+        write!(f, "max:  {:?}", self.percentile(100))
+    }
+}
@@ -0,0 +1,1053 @@
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::builder::{PossibleValue, PossibleValuesParser, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::{ArgAction, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::Shell;
+use clap_mangen::Man;
+use log::LevelFilter;
+
+use crate::data::{Assignment, AssignmentValue, Format};
+use crate::output::{Colour, Encoding, LineEndings, SplitMarker};
+use crate::render::Escape;
+use crate::render_dir::{Rename, Symlinks};
+use crate::report::ErrorFormat;
+use crate::{Command, Options, TemplateSource};
+
+/// The version shown by `--version`, along with the metadata of the build to tell apart binaries
+/// which behave differently.
+const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    "\ncommit: ",
+    env!("HANDLEBARS_CLI_GIT_COMMIT"),
+    "\nbuilt: ",
+    env!("HANDLEBARS_CLI_BUILD_DATE"),
+    "\nhandlebars: ",
+    env!("HANDLEBARS_CLI_HANDLEBARS_VERSION"),
+);
+
+/// Template JSON properties into Handlebars templates from the CLI.
+///
+/// Without a subcommand, the arguments are the same as for render, so running
+/// `handlebars-cli <JSON> <TEMPLATE>` renders the template.
+#[derive(Parser)]
+#[command(name = "handlebars-cli", version, long_version = LONG_VERSION)]
+#[command(arg_required_else_help = true, max_term_width = 100)]
+#[command(override_usage = "handlebars-cli [OPTIONS] [JSON]... <TEMPLATE>
+       handlebars-cli <COMMAND>")]
+struct Cli {
+    #[command(subcommand)]
+    command: CliCommand,
+
+    /// Logs what is being done to stderr, such as the properties loaded and the templates
+    /// registered. May be repeated for more detail with timings, and then for the logs of the
+    /// handlebars engine itself.
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Logs nothing but errors.
+    #[arg(short, long, conflicts_with = "verbose", global = true)]
+    quiet: bool,
+
+    /// How to report an error on stderr.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t,
+        global = true
+    )]
+    error_format: ErrorFormat,
+}
+
+/// An enumeration of the subcommands this program can be invoked with.
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Render a single template with the given properties.
+    #[command(override_usage = "handlebars-cli render [OPTIONS] [JSON]... <TEMPLATE>
+       handlebars-cli render [OPTIONS] [JSON]... --template-string <TEMPLATE>
+       handlebars-cli render [OPTIONS] [JSON]... --template-dir <PATH> --name <NAME>")]
+    Render(RenderArgs),
+    /// Render every template in a directory tree into the same place in an output tree.
+    #[command(
+        override_usage = "handlebars-cli render-dir [OPTIONS] --in <PATH> --out <PATH> [JSON]...
+       handlebars-cli render-dir [OPTIONS] --in <PATH> --archive <PATH> [JSON]..."
+    )]
+    RenderDir(RenderDirArgs),
+    /// List the paths of the properties a template uses, including those used by the partials it
+    /// includes from the template directories.
+    Vars(VarsArgs),
+    /// Print a JSON Schema of the properties a template uses, as best as can be told from how it
+    /// uses them: objects for dotted paths, arrays for #each and booleans for #if.
+    Schema(InspectArgs),
+    /// Print properties with a placeholder for every value a template uses, to render it with
+    /// straight away and fill in with real values bit by bit.
+    ExampleData(InspectArgs),
+    /// Print the tree of the partials a template includes from the template directories, and those
+    /// they include in turn, marking those which are missing or include themselves.
+    Deps(DepsArgs),
+    /// Print the elements of a template as the engine parsed them, such as its blocks, expressions
+    /// and partials, with the line and column each starts at.
+    Ast {
+        /// The path to the template, or - to read it from stdin.
+        template: String,
+
+        /// Prints the elements as JSON instead of one on each line.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render a template many times over with the same properties and report how long it took, to
+    /// compare ways of writing a template.
+    #[command(override_usage = "handlebars-cli bench [OPTIONS] [JSON]... <TEMPLATE>")]
+    Bench(BenchArgs),
+    /// List every helper which can be called inside templates, including those registered from
+    /// scripts, with how to call it and what it does.
+    Helpers(TemplateArgs),
+    /// Check that templates are valid without rendering them, reporting every one which is not
+    /// rather than stopping at the first.
+    Validate {
+        /// The paths to the templates to check.
+        #[arg(required = true, value_name = "TEMPLATE")]
+        templates: Vec<String>,
+    },
+    /// Print the completion script for a shell, to be sourced by its startup file.
+    Completions {
+        /// The shell to print the completion script for.
+        shell: Shell,
+    },
+    /// Print the manual page in roff format, for packagers to install.
+    #[command(hide = true)]
+    Man {
+        /// The subcommand to print the manual page for, instead of the one for the program.
+        subcommand: Option<String>,
+    },
+}
+
+/// An enumeration of the things this program can be asked to do.
+pub enum Action {
+    /// Render templates as described by the options.
+    Run(Box<Options>),
+    /// Print the completion script for the shell.
+    Completions(Shell),
+    /// Print the manual page for the program, or for one of its subcommands.
+    Man(Option<String>),
+}
+
+/// The arguments of the render subcommand.
+#[derive(Args)]
+struct RenderArgs {
+    /// Sets of valid JSON to use as properties, paths to files containing them, or - to read them
+    /// from stdin, deep merged in order so that later properties override earlier ones. Unless
+    /// --template-string or --name is given, the last is the path to a valid Handlebars template
+    /// instead, or - to read it from stdin. A template may start with YAML front matter between ---
+    /// lines, which is left out of the result and whose values are merged beneath the properties.
+    #[arg(value_name = "JSON")]
+    args: Vec<String>,
+
+    /// Renders the given template instead of reading it from a file, in which case every argument
+    /// is used as properties.
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        allow_hyphen_values = true,
+        conflicts_with = "name",
+        help_heading = "Templates"
+    )]
+    template_string: Option<String>,
+
+    /// Renders the template with this name from the template directories, in which case every
+    /// argument is used as properties.
+    #[arg(long, requires = "template_dir", help_heading = "Templates")]
+    name: Option<String>,
+
+    /// Writes the result to a file instead of stdout. The file is only replaced once rendering has
+    /// succeeded, and is never left partially written. The path may itself be a template rendered
+    /// with the properties, such as out/{{service.name}}.conf.
+    #[arg(
+        short,
+        long,
+        alias = "out",
+        value_name = "PATH",
+        help_heading = "Output"
+    )]
+    output: Option<PathBuf>,
+
+    /// Renders the template once for each element of the array at a dotted path in the
+    /// properties, with the element as the properties and @index, @first and @last available.
+    /// Combine with an --output path that is a template, such as out/{{id}}.html, to write each
+    /// result to its own file.
+    #[arg(long, value_name = "PATH", help_heading = "Output")]
+    each: Option<String>,
+
+    /// Splits the result into several files on lines matching the marker, such as
+    /// '--- file: <path>', writing each section to the path its marker line gives in place of
+    /// <path>. Anything before the first marker is written as usual unless it is blank.
+    #[arg(
+        long,
+        value_name = "MARKER",
+        value_parser = SplitMarker::parse,
+        allow_hyphen_values = true,
+        help_heading = "Output"
+    )]
+    split_marker: Option<SplitMarker>,
+
+    /// Renders the template inside the layout at a path, as though it was inside
+    /// {{#extend "layout"}}. The layout includes the rest of the template where it has
+    /// {{> @partial-block}}, and each of its {{#block "name"}}s is replaced by the block of the same
+    /// name in the template, if it has one.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "dev",
+        help_heading = "Templates"
+    )]
+    layout: Option<String>,
+
+    #[command(flatten)]
+    common: CommonArgs,
+}
+
+/// The arguments of the render-dir subcommand.
+#[derive(Args)]
+struct RenderDirArgs {
+    /// Sets of valid JSON to use as properties, paths to files containing them, or - to read them
+    /// from stdin, deep merged in order so that later properties override earlier ones.
+    #[arg(value_name = "JSON")]
+    args: Vec<String>,
+
+    /// The directory tree of templates to render. Every *.hbs file is rendered with the same
+    /// properties, along with the values of any front matter it starts with, and can use the others
+    /// as partials. A data file next to a template with the same name, such as page.json or
+    /// page.yaml for page.hbs, is merged over the properties for that template alone. Files and
+    /// directories matching the gitignore patterns of a .hbsignore file in the tree are skipped.
+    #[arg(long = "in", value_name = "PATH")]
+    input: PathBuf,
+
+    /// The directory tree to write the results to, at the same relative paths as the templates
+    /// without the .hbs extension. Like --output, may be a template.
+    #[arg(
+        short = 'o',
+        long = "out",
+        alias = "output",
+        value_name = "PATH",
+        required_unless_present = "archive"
+    )]
+    output: Option<PathBuf>,
+
+    /// Copies every file in the input tree without the .hbs extension to the output tree as it
+    /// is, such as images and other static assets, instead of skipping it.
+    #[arg(long)]
+    copy_static: bool,
+
+    /// Replaces the end of the names of templates matching FROM with TO in the names of the files
+    /// they are rendered to, such as .yaml.hbs=>.yaml or .hbs=>.txt, instead of only removing the
+    /// .hbs extension. The rule with the longest FROM that matches applies. May be repeated.
+    #[arg(
+        long,
+        value_name = "FROM=>TO",
+        value_parser = Rename::parse,
+        allow_hyphen_values = true
+    )]
+    rename: Vec<Rename>,
+
+    /// How to handle symlinks in the input tree.
+    #[arg(long, value_name = "POLICY", value_enum, default_value_t)]
+    symlinks: Symlinks,
+
+    #[command(flatten)]
+    common: CommonArgs,
+}
+
+/// The arguments of the bench subcommand.
+#[derive(Args)]
+struct BenchArgs {
+    /// Sets of valid JSON to use as properties, paths to files containing them, or - to read them
+    /// from stdin, deep merged in order so that later properties override earlier ones. The last
+    /// is the path to the template to render.
+    #[arg(value_name = "JSON", required = true)]
+    args: Vec<String>,
+
+    /// The number of renders to time.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1000,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    iterations: u64,
+
+    /// The number of renders before those timed, which are not timed themselves.
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    warmup: u64,
+
+    #[command(flatten)]
+    templates: TemplateArgs,
+
+    #[command(flatten)]
+    props: PropsArgs,
+}
+
+/// The arguments of the vars subcommand.
+#[derive(Args)]
+struct VarsArgs {
+    #[command(flatten)]
+    inspect: InspectArgs,
+
+    /// Prints the paths as a JSON array instead of one on each line.
+    #[arg(long)]
+    json: bool,
+}
+
+/// The arguments of the deps subcommand.
+#[derive(Args)]
+struct DepsArgs {
+    #[command(flatten)]
+    inspect: InspectArgs,
+
+    /// Prints the partials as a graph in the DOT language of Graphviz instead of as a tree.
+    #[arg(long)]
+    dot: bool,
+}
+
+/// The arguments shared by every subcommand which inspects a template instead of rendering it.
+#[derive(Args)]
+struct InspectArgs {
+    /// The path to the template, or - to read it from stdin.
+    template: String,
+
+    #[command(flatten)]
+    templates: TemplateArgs,
+}
+
+/// The arguments shared by every subcommand which renders templates.
+#[derive(Args)]
+struct CommonArgs {
+    #[command(flatten)]
+    templates: TemplateArgs,
+
+    /// Logs every helper called, partial expanded and property looked up to stderr as the templates
+    /// render, along with whether each property was found.
+    #[arg(long, help_heading = "Templates")]
+    trace: bool,
+
+    /// Reports the time spent loading the properties, compiling the templates, rendering and
+    /// writing to stderr, along with the time spent rendering each template and partial.
+    #[arg(long, help_heading = "Templates")]
+    profile: bool,
+
+    /// Leaves expressions such as {{name}} whose properties are not there in the result as they
+    /// are, for a later tool to fill in, instead of failing. Implies --lenient.
+    #[arg(long, conflicts_with = "strict", help_heading = "Templates")]
+    keep_missing: bool,
+
+    /// Reads templates, partials and helper scripts from disk again each time they are used instead
+    /// of once, to pick up changes made to them while rendering. The templates are then rendered as
+    /// they are on disk, so it cannot be combined with --layout or --max-include-depth, and
+    /// templates cannot start with front matter.
+    #[arg(
+        long,
+        conflicts_with_all = ["trace", "profile", "max_include_depth"],
+        help_heading = "Templates"
+    )]
+    dev: bool,
+
+    /// Fails once partials are included more than this many levels deep, such as a partial which
+    /// includes itself without end.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_MAX_INCLUDE_DEPTH,
+        help_heading = "Templates"
+    )]
+    max_include_depth: usize,
+
+    /// Renders values with {{name}} as they are, as {{{name}}} does, for output which is not HTML
+    /// such as shell scripts and configuration files. By default they are HTML-escaped, so that
+    /// characters such as & and " become &amp; and &quot;. The same as --escape none.
+    #[arg(long, conflicts_with = "escape", help_heading = "Templates")]
+    no_escape: bool,
+
+    /// How values rendered with {{name}} are escaped, to embed them safely in the format being
+    /// generated. Values rendered with {{{name}}} are never escaped.
+    #[arg(long, value_name = "MODE", value_enum, help_heading = "Templates")]
+    escape: Option<Escape>,
+
+    /// Seeds the values given by random_int, random_choice and random_string, so that they are the
+    /// same on every run, such as for tests and sample data. By default they are different every
+    /// time.
+    #[arg(long, value_name = "N", help_heading = "Templates")]
+    seed: Option<u64>,
+
+    /// Collects the files that would otherwise be written into a .tar, .tar.gz, .tgz or .zip
+    /// archive instead.
+    #[arg(long, value_name = "PATH", help_heading = "Output")]
+    archive: Option<PathBuf>,
+
+    /// The directory relative paths to write files to are relative to, instead of the current
+    /// directory.
+    #[arg(long, value_name = "PATH", help_heading = "Output")]
+    output_dir: Option<PathBuf>,
+
+    /// Creates the parent directories of the files written, or of the archive, if they do not
+    /// exist yet.
+    #[arg(long, help_heading = "Output")]
+    mkdir: bool,
+
+    /// Converts the line endings of the results, whatever those of the templates are, overriding the
+    /// configuration file. Native is crlf on Windows and lf elsewhere.
+    #[arg(long, value_name = "ENDINGS", value_enum, help_heading = "Output")]
+    line_endings: Option<LineEndings>,
+
+    /// The encoding to write files in. Results written to stdout are always UTF-8.
+    #[arg(
+        long,
+        value_name = "ENCODING",
+        value_enum,
+        default_value_t,
+        help_heading = "Output"
+    )]
+    output_encoding: Encoding,
+
+    /// Writes the result to stdout without a newline after it.
+    #[arg(long, help_heading = "Output")]
+    no_trailing_newline: bool,
+
+    /// Removes the lines of the results which are empty or only whitespace, such as those left
+    /// behind by {{#if}} blocks on lines of their own.
+    #[arg(long, help_heading = "Output")]
+    strip_blank_lines: bool,
+
+    /// When bold, red, color and the other styling helpers colour their text with ANSI escape
+    /// codes. By default they do only when the result is written to a terminal, and never when it
+    /// is written to files.
+    #[arg(
+        long = "color",
+        value_name = "WHEN",
+        value_enum,
+        default_value_t,
+        help_heading = "Output"
+    )]
+    colour: Colour,
+
+    #[command(flatten)]
+    props: PropsArgs,
+}
+
+/// The arguments for how the templates are registered, shared by every subcommand which reads
+/// templates.
+#[derive(Args, Default)]
+struct TemplateArgs {
+    /// Registers every *.hbs file in a directory as a template named after its relative path
+    /// without the extension, such as email/welcome, so that templates can use each other as
+    /// partials. May be repeated to layer directories, such as a base set and an overlay, with the
+    /// templates of later directories taking the place of those at the same relative path in
+    /// earlier ones.
+    #[arg(long, value_name = "PATH", help_heading = "Templates")]
+    template_dir: Vec<PathBuf>,
+
+    /// Registers every *.hbs file in a directory as a partial named after its relative path
+    /// without the extension, so that {{> header}} includes header.hbs. Registered before the
+    /// template directories. May be repeated.
+    #[arg(long, value_name = "PATH", help_heading = "Templates")]
+    partials: Vec<PathBuf>,
+
+    /// Registers the template at a path as the partial with the given name, such as
+    /// header=layout/site-header.hbs, for partials which are not in a directory of their own or
+    /// need another name. Registered after the directories, so that they take precedence. May be
+    /// repeated.
+    #[arg(
+        long,
+        value_name = "NAME=PATH",
+        value_parser = |raw: &str| parse_named_path(raw, "partial"),
+        help_heading = "Templates"
+    )]
+    partial: Vec<(String, PathBuf)>,
+
+    /// Fails on properties the templates use that are not there, even if the configuration file
+    /// turns this off. This is the default.
+    #[arg(long, help_heading = "Templates")]
+    strict: bool,
+
+    /// Renders properties the templates use that are not there as empty, as stock Handlebars does,
+    /// instead of failing, even if the configuration file turns strict mode on.
+    #[arg(long, conflicts_with = "strict", help_heading = "Templates")]
+    lenient: bool,
+
+    /// Registers the Rhai script at a path as the helper with the given name, such as
+    /// shout=helpers/shout.rhai, taking precedence over the helpers of the configuration file.
+    /// May be repeated.
+    #[arg(
+        long,
+        value_name = "NAME=PATH",
+        value_parser = |raw: &str| parse_named_path(raw, "helper"),
+        help_heading = "Templates"
+    )]
+    helper: Vec<(String, PathBuf)>,
+
+    /// Ignores the configuration files, both the one at ~/.config/handlebars-cli/config.toml (or
+    /// under $XDG_CONFIG_HOME if it is set) and the .handlebarsrc.toml of the project.
+    #[arg(long)]
+    no_config: bool,
+}
+
+/// The arguments for where the properties come from.
+#[derive(Args)]
+struct PropsArgs {
+    /// The format of the properties. Defaults to json, or to the format matching the file
+    /// extension when the properties are read from a file.
+    #[arg(long, value_parser = formats(), help_heading = "Properties")]
+    format: Option<Format>,
+
+    /// Parses the properties leniently as JSON5, the same as --format json5.
+    #[arg(long, conflicts_with = "format", help_heading = "Properties")]
+    json5: bool,
+
+    /// Reads properties from a file, deep merged over any JSON arguments. May be repeated, with
+    /// later files overriding earlier ones.
+    #[arg(long, value_name = "PATH", help_heading = "Properties")]
+    data_file: Vec<PathBuf>,
+
+    /// Reads default properties from a file, which are only used where the other properties do not
+    /// set a value, so that a template can ship with defaults for what callers leave out. May be
+    /// repeated, with later files overriding earlier ones.
+    #[arg(long, value_name = "PATH", help_heading = "Properties")]
+    defaults: Vec<PathBuf>,
+
+    /// Reads every properties file of a known format in a directory, each under a key named after
+    /// the file, so users.json becomes users. Subdirectories become nested objects. Merged beneath
+    /// any other properties. May be repeated.
+    #[arg(long, value_name = "PATH", help_heading = "Properties")]
+    data_dir: Vec<PathBuf>,
+
+    /// Reads properties from a file, guessing the format from its extension, and mounts them under
+    /// their own key such as db instead of merging them. May be repeated, and is applied in order
+    /// with --set.
+    #[arg(
+        long,
+        value_name = "NAME=PATH",
+        value_parser = |raw: &str| Assignment::parse(raw, AssignmentValue::Props),
+        help_heading = "Properties"
+    )]
+    context: Vec<Assignment>,
+
+    /// Builds the properties from string values at dotted keys such as user.name, without the need
+    /// for any JSON. May be repeated, and may be combined with other properties.
+    #[arg(
+        long,
+        value_name = "KEY=VALUE",
+        value_parser = |raw: &str| Assignment::parse(raw, AssignmentValue::String),
+        help_heading = "Properties"
+    )]
+    kv: Vec<Assignment>,
+
+    /// Sets the string value at a dotted path such as server.host in the properties after they
+    /// have been loaded, overriding what was there. May be repeated.
+    #[arg(
+        long,
+        value_name = "PATH=VALUE",
+        value_parser = |raw: &str| Assignment::parse(raw, AssignmentValue::String),
+        help_heading = "Properties"
+    )]
+    set: Vec<Assignment>,
+
+    /// Like --set, but parses the value as JSON so that numbers, booleans, arrays and objects can
+    /// be set. May be repeated, and is applied in order with --set.
+    #[arg(
+        long,
+        value_name = "PATH=JSON",
+        value_parser = |raw: &str| Assignment::parse(raw, AssignmentValue::Json),
+        help_heading = "Properties"
+    )]
+    set_json: Vec<Assignment>,
+
+    /// Like --set, but uses the contents of a file as the string value. May be repeated, and is
+    /// applied in order with --set.
+    #[arg(
+        long,
+        value_name = "PATH=FILE",
+        value_parser = |raw: &str| Assignment::parse(raw, AssignmentValue::File),
+        help_heading = "Properties"
+    )]
+    set_file: Vec<Assignment>,
+
+    /// Renders the strings in the properties which contain {{...}}, such as
+    /// "url": "https://{{host}}:{{port}}", as templates with the properties themselves, before
+    /// rendering the template. Strings referring to others which are templates are rendered again,
+    /// up to DEPTH times over, 8 by default.
+    #[arg(
+        long,
+        value_name = "DEPTH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "8",
+        help_heading = "Properties"
+    )]
+    render_values: Option<usize>,
+
+    /// Merges the variables of a dotenv file into the top level of the properties, overriding
+    /// properties of the same name. May be repeated.
+    #[arg(long, value_name = "PATH", help_heading = "Properties")]
+    dotenv: Vec<PathBuf>,
+
+    /// Exposes the variables of the environment under env in the properties, such as env.HOME.
+    #[arg(long, help_heading = "Properties")]
+    env: bool,
+
+    /// Like --env, but only exposes the variables whose names start with the prefix, such as APP_.
+    #[arg(long, value_name = "PREFIX", help_heading = "Properties")]
+    env_prefix: Option<String>,
+}
+
+/// The formats the properties can be in, along with the other names they can be given by.
+fn formats() -> impl TypedValueParser<Value = Format> {
+    PossibleValuesParser::new([
+        PossibleValue::new("json"),
+        PossibleValue::new("json5"),
+        PossibleValue::new("yaml").alias("yml"),
+        PossibleValue::new("toml"),
+        PossibleValue::new("msgpack").alias("mpk"),
+        PossibleValue::new("cbor"),
+        PossibleValue::new("hcl")
+            .alias("tfvars")
+            .help("Also used for .tfvars files. Expressions become strings like ${var.name}"),
+        PossibleValue::new("csv")
+            .help("Records become an array of objects keyed by header under rows"),
+        PossibleValue::new("ini")
+            .alias("properties")
+            .help("Sections become nested objects"),
+        PossibleValue::new("xml").help(
+            "Elements become objects with their attributes prefixed by @, their children by name \
+             and their text under #text",
+        ),
+    ])
+    .try_map(|raw| raw.parse::<Format>())
+}
+
+/// Parse the name and path of a helper script or partial given as NAME=PATH.
+fn parse_named_path(raw: &str, kind: &str) -> Result<(String, PathBuf), String> {
+    match raw.split_once('=') {
+        Some((name, path)) if !name.is_empty() => Ok((name.to_owned(), PathBuf::from(path))),
+        _ => Err(format!("Invalid {} '{}', expected NAME=PATH.", kind, raw)),
+    }
+}
+
+/// How many levels deep partials can be included by default.
+const DEFAULT_MAX_INCLUDE_DEPTH: usize = 64;
+
+/// The names of the arguments which assign properties, which are applied in the order they were
+/// given in regardless of which of them they are.
+const ASSIGNMENTS: [&str; 5] = ["context", "kv", "set", "set_json", "set_file"];
+
+/// Whether an argument only sets an option in front of the subcommand that applies to all of them,
+/// such as -v, -vv, --quiet or --error-format=json, but not an option followed by its value.
+fn is_global_option(arg: &OsString) -> bool {
+    let arg = arg.to_string_lossy();
+    match arg.strip_prefix('-') {
+        Some("-verbose" | "-quiet") => true,
+        Some(option) if option.starts_with("-error-format=") => true,
+        Some(flags) => !flags.is_empty() && flags.chars().all(|flag| matches!(flag, 'v' | 'q')),
+        None => false,
+    }
+}
+
+/// Parse what to do from the given command line arguments, including the program name.
+///
+/// When the first argument after the global options is not a subcommand, it is parsed as though it
+/// were the render subcommand, so that invocations from before there were subcommands keep
+/// working.
+///
+/// It fails with the error to show the user if the arguments are not understood, or if help or
+/// the version was asked for.
+pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Action, clap::Error> {
+    let mut args = args.into_iter().collect::<Vec<_>>();
+    let command = Cli::command();
+    let is_subcommand = |arg: &OsString| {
+        let arg = arg.to_string_lossy();
+        ["-h", "--help", "-V", "--version", "help"].contains(&arg.as_ref())
+            || command.find_subcommand(arg.as_ref()).is_some()
+    };
+    let mut first = 1;
+    while let Some(arg) = args.get(first) {
+        if arg == "--error-format" {
+            first += 2;
+        } else if is_global_option(arg) {
+            first += 1;
+        } else {
+            break;
+        }
+    }
+    if args.len() > 1 && !args.get(first).is_some_and(is_subcommand) {
+        args.insert(1, OsString::from("render"));
+    }
+
+    let matches = command.try_get_matches_from(args)?;
+    let cli = Cli::from_arg_matches(&matches)?;
+    let log_level = match (cli.quiet, cli.verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Warn,
+        (false, 1) => LevelFilter::Info,
+        (false, 2) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    let sub_matches = matches
+        .subcommand()
+        .map_or(&matches, |(_, matches)| matches);
+
+    match cli.command {
+        CliCommand::Render(render) => {
+            let mut args = render.args;
+            let template = match (render.template_string, render.name) {
+                (Some(template), _) => TemplateSource::Inline(template),
+                (None, Some(name)) => TemplateSource::Named(name),
+                (None, None) => {
+                    let raw_filename = args.pop().ok_or_else(|| {
+                        usage_error("The path to the template to render is required.", "render")
+                    })?;
+                    if raw_filename == "-" && args.iter().any(|raw_props| raw_props == "-") {
+                        return Err(usage_error(
+                            "Only one of the properties and the template can be read from stdin.",
+                            "render",
+                        ));
+                    }
+                    TemplateSource::File(raw_filename)
+                }
+            };
+            let command = Command::Render(template);
+
+            let common = options(
+                sub_matches,
+                render.common,
+                args,
+                command,
+                log_level,
+                cli.error_format,
+            )?;
+            // Without an output path or split marker, the result is written to stdout and there
+            // would be nothing to put in the archive.
+            if common.archive.is_some() && render.output.is_none() && render.split_marker.is_none()
+            {
+                return Err(usage_error(
+                    "An --output path or a --split-marker is required to write to an --archive.",
+                    "render",
+                ));
+            }
+            Ok(Action::Run(Box::new(Options {
+                output: render.output,
+                each: render.each,
+                layout: render.layout,
+                split_marker: render.split_marker,
+                ..common
+            })))
+        }
+        CliCommand::RenderDir(render_dir) => {
+            let command = Command::RenderDir {
+                input: render_dir.input,
+                output: render_dir.output,
+            };
+            let args = render_dir.args;
+            let common = options(
+                sub_matches,
+                render_dir.common,
+                args,
+                command,
+                log_level,
+                cli.error_format,
+            )?;
+            Ok(Action::Run(Box::new(Options {
+                copy_static: render_dir.copy_static,
+                renames: render_dir.rename,
+                symlinks: render_dir.symlinks,
+                ..common
+            })))
+        }
+        CliCommand::Bench(bench) => {
+            let mut args = bench.args;
+            let template = args.pop().unwrap_or_default();
+            let command = Command::Bench {
+                template: TemplateSource::File(template),
+                warmup: bench.warmup,
+                iterations: bench.iterations,
+            };
+            let common = CommonArgs {
+                templates: bench.templates,
+                trace: false,
+                profile: false,
+                keep_missing: false,
+                max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+                dev: false,
+                no_escape: false,
+                escape: None,
+                seed: None,
+                archive: None,
+                output_dir: None,
+                mkdir: false,
+                no_trailing_newline: false,
+                line_endings: None,
+                output_encoding: Encoding::default(),
+                strip_blank_lines: false,
+                colour: Colour::Never,
+                props: bench.props,
+            };
+            let options = options(
+                sub_matches,
+                common,
+                args,
+                command,
+                log_level,
+                cli.error_format,
+            )?;
+            Ok(Action::Run(Box::new(options)))
+        }
+        CliCommand::Vars(vars) => {
+            let command = Command::Vars {
+                template: TemplateSource::File(vars.inspect.template),
+                json: vars.json,
+            };
+            let templates = vars.inspect.templates;
+            let options = template_options(templates, command, log_level, cli.error_format);
+            Ok(Action::Run(Box::new(options)))
+        }
+        CliCommand::Schema(inspect) => {
+            let command = Command::Schema(TemplateSource::File(inspect.template));
+            let templates = inspect.templates;
+            let options = template_options(templates, command, log_level, cli.error_format);
+            Ok(Action::Run(Box::new(options)))
+        }
+        CliCommand::ExampleData(inspect) => {
+            let command = Command::ExampleData(TemplateSource::File(inspect.template));
+            let templates = inspect.templates;
+            let options = template_options(templates, command, log_level, cli.error_format);
+            Ok(Action::Run(Box::new(options)))
+        }
+        CliCommand::Deps(deps) => {
+            let command = Command::Deps {
+                template: TemplateSource::File(deps.inspect.template),
+                dot: deps.dot,
+            };
+            let templates = deps.inspect.templates;
+            let options = template_options(templates, command, log_level, cli.error_format);
+            Ok(Action::Run(Box::new(options)))
+        }
+        CliCommand::Ast { template, json } => {
+            let command = Command::Ast {
+                template: TemplateSource::File(template),
+                json,
+            };
+            let options = template_options(
+                TemplateArgs::default(),
+                command,
+                log_level,
+                cli.error_format,
+            );
+            Ok(Action::Run(Box::new(options)))
+        }
+        CliCommand::Helpers(templates) => {
+            let command = Command::Helpers;
+            let options = template_options(templates, command, log_level, cli.error_format);
+            Ok(Action::Run(Box::new(options)))
+        }
+        CliCommand::Validate { templates } => {
+            let command = Command::Validate(templates);
+            let options = template_options(
+                TemplateArgs::default(),
+                command,
+                log_level,
+                cli.error_format,
+            );
+            Ok(Action::Run(Box::new(options)))
+        }
+        CliCommand::Completions { shell } => Ok(Action::Completions(shell)),
+        CliCommand::Man { subcommand } => match subcommand {
+            Some(name) if Cli::command().find_subcommand(&name).is_none() => Err(usage_error(
+                &format!(
+                    "There is no subcommand '{}' to print the manual page for.",
+                    name
+                ),
+                "man",
+            )),
+            subcommand => Ok(Action::Man(subcommand)),
+        },
+    }
+}
+
+/// Write the manual page for the program, or for the subcommand with the given name, generated
+/// from the same definitions the arguments are parsed with.
+pub fn man(subcommand: Option<&str>, out: &mut impl Write) -> io::Result<()> {
+    let mut command = Cli::command();
+    command.build();
+    match subcommand.and_then(|name| command.find_subcommand(name)) {
+        Some(subcommand) => Man::new(subcommand.clone())
+            .title(format!("handlebars-cli-{}", subcommand.get_name()))
+            .render(out),
+        None => Man::new(command).render(out),
+    }
+}
+
+/// Write the completion script for the shell, generated from the same definitions the arguments
+/// are parsed with.
+pub fn completions(shell: Shell, out: &mut impl Write) {
+    clap_complete::generate(shell, &mut Cli::command(), "handlebars-cli", out);
+}
+
+/// Build the options for a subcommand from the arguments it shares with the others, leaving those
+/// only some of them have unset.
+fn options(
+    matches: &ArgMatches,
+    common: CommonArgs,
+    raw_props: Vec<String>,
+    command: Command,
+    log_level: LevelFilter,
+    error_format: ErrorFormat,
+) -> Result<Options, clap::Error> {
+    let props = common.props;
+    let env = props.env;
+    let mut assignments = ASSIGNMENTS
+        .iter()
+        .flat_map(|id| {
+            let values = matches.get_many::<Assignment>(id).into_iter().flatten();
+            let indices = matches.indices_of(id).into_iter().flatten();
+            indices.zip(values.cloned())
+        })
+        .collect::<Vec<_>>();
+    assignments.sort_by_key(|(index, _)| *index);
+    let props_given = !raw_props.is_empty()
+        || !props.data_file.is_empty()
+        || !props.data_dir.is_empty()
+        || !props.dotenv.is_empty()
+        || !assignments.is_empty()
+        || env
+        || props.env_prefix.is_some();
+
+    Ok(Options {
+        format: if props.json5 {
+            Some(Format::Json5)
+        } else {
+            props.format
+        },
+        dotenv: props.dotenv,
+        env_prefix: props.env_prefix.or_else(|| env.then(String::new)),
+        data_files: props.data_file,
+        data_dirs: props.data_dir,
+        render_values: props.render_values,
+        assignments: assignments
+            .into_iter()
+            .map(|(_, assignment)| assignment)
+            .collect(),
+        raw_props,
+        mkdir: common.mkdir,
+        no_trailing_newline: common.no_trailing_newline,
+        line_endings: common.line_endings,
+        output_encoding: common.output_encoding,
+        strip_blank_lines: common.strip_blank_lines,
+        archive: common.archive,
+        props_given,
+        output_dir: common.output_dir,
+        default_data_files: props.defaults,
+        trace: common.trace,
+        profile: common.profile,
+        keep_missing: common.keep_missing,
+        max_include_depth: common.max_include_depth,
+        dev: common.dev,
+        escape: if common.no_escape {
+            Escape::None
+        } else {
+            common.escape.unwrap_or_default()
+        },
+        seed: common.seed,
+        colour: common.colour,
+        ..template_options(common.templates, command, log_level, error_format)
+    })
+}
+
+/// Build the options for a subcommand which only reads templates, leaving those for the
+/// properties and the output unset.
+fn template_options(
+    templates: TemplateArgs,
+    command: Command,
+    log_level: LevelFilter,
+    error_format: ErrorFormat,
+) -> Options {
+    Options {
+        format: None,
+        dotenv: Vec::new(),
+        env_prefix: None,
+        data_files: Vec::new(),
+        data_dirs: Vec::new(),
+        assignments: Vec::new(),
+        render_values: None,
+        raw_props: Vec::new(),
+        command,
+        template_dirs: templates
+            .partials
+            .into_iter()
+            .chain(templates.template_dir)
+            .collect(),
+        output: None,
+        mkdir: false,
+        no_trailing_newline: false,
+        strip_blank_lines: false,
+        each: None,
+        layout: None,
+        split_marker: None,
+        archive: None,
+        copy_static: false,
+        renames: Vec::new(),
+        symlinks: Symlinks::default(),
+        strict: if templates.lenient {
+            Some(false)
+        } else {
+            templates.strict.then_some(true)
+        },
+        partials: templates.partial,
+        helpers: templates.helper,
+        line_endings: None,
+        output_encoding: Encoding::default(),
+        no_config: templates.no_config,
+        props_given: false,
+        default_data_files: Vec::new(),
+        output_dir: None,
+        log_level,
+        error_format,
+        trace: false,
+        profile: false,
+        keep_missing: false,
+        max_include_depth: DEFAULT_MAX_INCLUDE_DEPTH,
+        dev: false,
+        escape: Escape::default(),
+        seed: None,
+        colour: Colour::default(),
+    }
+}
+
+/// The error for no properties being given at all to render the templates with, neither on the
+/// command line nor by the configuration, for the commands which render templates.
+pub fn props_required(command: &Command) -> Option<clap::Error> {
+    let subcommand = match command {
+        Command::Render(_) => "render",
+        Command::RenderDir { .. } => "render-dir",
+        Command::Bench { .. } => "bench",
+        Command::Vars { .. }
+        | Command::Schema(_)
+        | Command::ExampleData(_)
+        | Command::Deps { .. }
+        | Command::Ast { .. }
+        | Command::Helpers
+        | Command::Validate(_) => return None,
+    };
+    Some(usage_error(
+        "Some properties are required, as JSON or with --data-file, --data-dir, --kv or --context.",
+        subcommand,
+    ))
+}
+
+/// An error for the arguments not making sense together, shown with the usage of the subcommand.
+fn usage_error(message: &str, subcommand: &str) -> clap::Error {
+    let mut command = Cli::command();
+    command.build();
+    match command.find_subcommand_mut(subcommand) {
+        Some(subcommand) => subcommand.error(ErrorKind::MissingRequiredArgument, message),
+        None => command.error(ErrorKind::MissingRequiredArgument, message),
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::info;
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+use snafu::ResultExt;
+
+use crate::output::LineEndings;
+use crate::{ConfigInvalidSnafu, ConfigUnreadableSnafu, EnvVarInvalidSnafu, Result};
+
+/// The persistent defaults read from the configuration files, which the command line overrides.
+///
+/// Relative paths in a file are relative to the directory the file is in.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Whether to fail on properties the templates use that are not there.
+    pub strict: Option<bool>,
+    /// The template directories registered before any given on the command line.
+    pub template_dirs: Vec<PathBuf>,
+    /// The directories of partials registered before the template directories.
+    pub partial_dirs: Vec<PathBuf>,
+    /// The scripts registered as helpers, by the name of the helper.
+    pub helpers: BTreeMap<String, PathBuf>,
+    /// The line endings to convert the rendered results to.
+    pub line_endings: Option<LineEndings>,
+    /// The data files merged beneath any other properties.
+    pub data_files: Vec<PathBuf>,
+    /// The directory relative paths to write files to are relative to.
+    pub output_dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// Load the configuration file from the user's configuration directory, following the XDG
+    /// base directory specification, and then the `.handlebarsrc.toml` of the project, found in
+    /// the current directory or the closest of its parents, which takes precedence. Either may be
+    /// missing.
+    ///
+    /// The `HANDLEBARS_CLI_*` environment variables are layered over both.
+    ///
+    /// It fails if a file exists but could not be read or is not valid.
+    /// It fails if an environment variable is not valid.
+    pub fn load() -> Result<Config> {
+        let mut config = Config::default();
+        let paths = user_config_path().into_iter().chain(project_config_path());
+        for path in paths.filter(|path| path.is_file()) {
+            config = config.merge(Config::load_file(&path)?);
+        }
+        Ok(config.merge(Config::from_env()?))
+    }
+
+    /// Read the configuration from the `HANDLEBARS_CLI_*` environment variables, where those
+    /// holding directories or files may hold several separated as in `PATH`.
+    ///
+    /// It fails if an environment variable is not valid.
+    pub fn from_env() -> Result<Config> {
+        let paths = |name: &str| {
+            env::var_os(format!("{}{}", ENV_PREFIX, name))
+                .map(|raw| env::split_paths(&raw).collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+
+        Ok(Config {
+            strict: env_var("STRICT", |raw| match raw.to_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Some(true),
+                "false" | "0" | "no" | "off" => Some(false),
+                _ => None,
+            })?,
+            template_dirs: paths("TEMPLATE_DIR"),
+            partial_dirs: paths("PARTIALS_DIR"),
+            helpers: BTreeMap::new(),
+            line_endings: env_var("LINE_ENDINGS", |raw| {
+                LineEndings::deserialize(raw.into_deserializer())
+                    .map_err(|_: serde::de::value::Error| ())
+                    .ok()
+            })?,
+            data_files: paths("DATA_FILE"),
+            output_dir: env::var_os(format!("{}OUTPUT_DIR", ENV_PREFIX)).map(PathBuf::from),
+        })
+    }
+
+    /// Layer another configuration over this one. Its settings take precedence, while its
+    /// directories and data files come after these, and its helpers replace any of the same name.
+    fn merge(mut self, other: Config) -> Config {
+        self.strict = other.strict.or(self.strict);
+        self.line_endings = other.line_endings.or(self.line_endings);
+        self.template_dirs.extend(other.template_dirs);
+        self.partial_dirs.extend(other.partial_dirs);
+        self.data_files.extend(other.data_files);
+        self.helpers.extend(other.helpers);
+        self.output_dir = other.output_dir.or(self.output_dir);
+        self
+    }
+
+    /// Load the configuration file at the given path.
+    ///
+    /// It fails if the file could not be read or is not valid.
+    fn load_file(path: &Path) -> Result<Config> {
+        let raw = fs::read_to_string(path).context(ConfigUnreadableSnafu { path })?;
+        info!("Loaded configuration from '{}'", path.display());
+        let config: Config = toml::from_str(&raw).context(ConfigInvalidSnafu { path })?;
+        Ok(config.relative_to(path.parent().unwrap_or_else(|| Path::new("."))))
+    }
+
+    /// Resolve the relative paths in the configuration against the given directory.
+    fn relative_to(mut self, dir: &Path) -> Config {
+        let resolve = |path: &mut PathBuf| *path = dir.join(&*path);
+        self.template_dirs.iter_mut().for_each(resolve);
+        self.partial_dirs.iter_mut().for_each(resolve);
+        self.helpers.values_mut().for_each(resolve);
+        self.data_files.iter_mut().for_each(resolve);
+        self.output_dir.iter_mut().for_each(resolve);
+        self
+    }
+}
+
+/// The prefix of the environment variables configuring this program.
+const ENV_PREFIX: &str = "HANDLEBARS_CLI_";
+
+/// Parse the value of the environment variable with the given name after the prefix, if it is set.
+///
+/// It fails if the value could not be parsed.
+fn env_var<T>(name: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Option<T>> {
+    let name = format!("{}{}", ENV_PREFIX, name);
+    match env::var(&name) {
+        Ok(value) => match parse(&value) {
+            Some(parsed) => Ok(Some(parsed)),
+            None => EnvVarInvalidSnafu { name, value }.fail(),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+/// The name of the configuration file of a project.
+const PROJECT_CONFIG_NAME: &str = ".handlebarsrc.toml";
+
+/// The path of the configuration file of the project the current directory is in, if there is one
+/// in it or any of its parents.
+fn project_config_path() -> Option<PathBuf> {
+    let dir = env::current_dir().ok()?;
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The path of the user's configuration file, under `$XDG_CONFIG_HOME`, or `~/.config` if that is
+/// not set.
+fn user_config_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(dir.join("handlebars-cli").join("config.toml"))
+}
@@ -0,0 +1,471 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::{self, FromStr};
+
+use ini::Ini;
+use log::info;
+use quick_xml::events::{BytesStart, Event};
+use serde_json::value::{Map, Value as Json};
+use snafu::ResultExt;
+
+use crate::{
+    AssignmentFileUnreadableSnafu, AssignmentInvalidJsonSnafu, DataDirUnreadableSnafu,
+    DotenvInvalidSnafu, Error, InvalidAssignmentSnafu, PropsInvalidCborSnafu, PropsInvalidCsvSnafu,
+    PropsInvalidHclSnafu, PropsInvalidIniSnafu, PropsInvalidJson5Snafu, PropsInvalidJsonSnafu,
+    PropsInvalidMsgpackSnafu, PropsInvalidTomlSnafu, PropsInvalidXmlSnafu, PropsInvalidYamlSnafu,
+    PropsNotAnObjectSnafu, PropsNotUtf8Snafu, PropsUnreadableSnafu, Result, StdinUnreadableSnafu,
+    UnknownFormatSnafu,
+};
+
+/// An enumeration of the formats the properties can be provided in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Json5,
+    Yaml,
+    Toml,
+    Csv,
+    Ini,
+    Xml,
+    Msgpack,
+    Cbor,
+    Hcl,
+}
+
+impl Format {
+    /// Guess the format of a properties file from its extension, if it is one we know about.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+
+    /// Parse the given raw properties, which are expected to be in this format, into the JSON
+    /// value used as the rendering context.
+    pub fn parse(self, raw: &[u8]) -> Result<Json> {
+        match self {
+            Format::Json => serde_json::from_slice(raw).context(PropsInvalidJsonSnafu {}),
+            Format::Json5 => {
+                let raw = str::from_utf8(raw).context(PropsNotUtf8Snafu {})?;
+                json5::from_str(raw).context(PropsInvalidJson5Snafu {})
+            }
+            Format::Yaml => serde_yaml::from_slice(raw).context(PropsInvalidYamlSnafu {}),
+            Format::Toml => {
+                let raw = str::from_utf8(raw).context(PropsNotUtf8Snafu {})?;
+                toml::from_str(raw).with_context(|err| {
+                    let offset = err.span().map(|span| span.start).unwrap_or(0);
+                    let (line, column) = line_and_column(raw, offset);
+                    let message = match err.message().trim() {
+                        "" => "invalid syntax".to_owned(),
+                        message => message.replace('\n', ", "),
+                    };
+                    PropsInvalidTomlSnafu {
+                        line,
+                        column,
+                        message,
+                    }
+                })
+            }
+            Format::Csv => parse_csv(raw),
+            Format::Ini => {
+                let raw = str::from_utf8(raw).context(PropsNotUtf8Snafu {})?;
+                parse_ini(raw)
+            }
+            Format::Xml => parse_xml(raw).context(PropsInvalidXmlSnafu {}),
+            Format::Msgpack => rmp_serde::from_slice(raw).context(PropsInvalidMsgpackSnafu {}),
+            Format::Cbor => ciborium::from_reader(raw).context(PropsInvalidCborSnafu {}),
+            Format::Hcl => {
+                let raw = str::from_utf8(raw).context(PropsNotUtf8Snafu {})?;
+                hcl::from_str(raw).context(PropsInvalidHclSnafu {})
+            }
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(raw: &str) -> Result<Format> {
+        match raw {
+            "json" => Ok(Format::Json),
+            "json5" => Ok(Format::Json5),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "csv" => Ok(Format::Csv),
+            "ini" | "properties" => Ok(Format::Ini),
+            "xml" => Ok(Format::Xml),
+            "msgpack" | "mpk" => Ok(Format::Msgpack),
+            "cbor" => Ok(Format::Cbor),
+            "hcl" | "tfvars" => Ok(Format::Hcl),
+            _ => UnknownFormatSnafu { format: raw }.fail(),
+        }
+    }
+}
+
+/// Load the properties given on the command line into a JSON value.
+///
+/// If the raw properties are `-`, they are read from stdin until EOF. If they are the path of an
+/// existing file, that file is read instead and its format is guessed from its extension unless one
+/// was given explicitly. Otherwise the raw properties themselves are parsed, as JSON unless another
+/// format was given.
+pub fn load_props(raw_props: &str, format: Option<Format>) -> Result<Json> {
+    if raw_props == "-" {
+        let mut raw = Vec::new();
+        io::stdin()
+            .read_to_end(&mut raw)
+            .context(StdinUnreadableSnafu {})?;
+        info!("Loaded properties from stdin");
+        return format.unwrap_or(Format::Json).parse(&raw);
+    }
+
+    let path = Path::new(raw_props);
+    if path.is_file() {
+        return load_props_file(path, format);
+    }
+
+    format.unwrap_or(Format::Json).parse(raw_props.as_bytes())
+}
+
+/// Load the properties from the file at the given path into a JSON value, guessing the format from
+/// its extension unless one was given explicitly.
+pub fn load_props_file(path: &Path, format: Option<Format>) -> Result<Json> {
+    let raw = fs::read(path).context(PropsUnreadableSnafu { path })?;
+    let format = format.or_else(|| Format::from_path(path));
+    info!("Loaded properties from '{}'", path.display());
+    format.unwrap_or(Format::Json).parse(&raw)
+}
+
+/// Load every properties file of a known format in a directory into an object, with each file
+/// under a key named after it without its extension. Subdirectories become nested objects in the
+/// same way.
+pub fn load_props_dir(path: &Path, format: Option<Format>) -> Result<Json> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect()
+        })
+        .context(DataDirUnreadableSnafu { path })?;
+    entries.sort();
+
+    let mut props = Map::new();
+    for entry in entries {
+        let key = match entry.file_stem().and_then(|stem| stem.to_str()) {
+            Some(key) => key.to_owned(),
+            None => continue,
+        };
+
+        let value = if entry.is_dir() {
+            load_props_dir(&entry, format)?
+        } else if Format::from_path(&entry).is_some() {
+            load_props_file(&entry, format)?
+        } else {
+            continue;
+        };
+        deep_merge(props.entry(key).or_insert(Json::Null), value);
+    }
+
+    Ok(Json::Object(props))
+}
+
+/// Load the variables of a dotenv file as an object of strings.
+pub fn load_dotenv(path: &Path) -> Result<Map<String, Json>> {
+    info!("Loading dotenv file '{}'", path.display());
+    dotenvy::from_path_iter(path)
+        .and_then(|vars| {
+            vars.map(|var| var.map(|(key, value)| (key, Json::String(value))))
+                .collect()
+        })
+        .context(DotenvInvalidSnafu { path })
+}
+
+/// Deep merge the overlay into the base, so that objects are merged key by key and any other value
+/// in the overlay replaces the one in the base.
+pub fn deep_merge(base: &mut Json, overlay: Json) {
+    match (base, overlay) {
+        (Json::Object(base), Json::Object(overlay)) => {
+            for (key, value) in overlay {
+                deep_merge(base.entry(key).or_insert(Json::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Collect the variables of the process environment whose names start with the given prefix as an
+/// object of strings, skipping any that are not valid unicode.
+pub fn environment(prefix: &str) -> Map<String, Json> {
+    env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .filter(|(key, _)| key.starts_with(prefix))
+        .map(|(key, value)| (key, Json::String(value)))
+        .collect()
+}
+
+/// Insert the given values into the top level of the properties, replacing any existing values
+/// with the same keys.
+///
+/// It fails if the properties are not an object.
+pub fn merge_top_level(props: &mut Json, values: Map<String, Json>) -> Result<()> {
+    match props {
+        Json::Object(props) => {
+            props.extend(values);
+            Ok(())
+        }
+        _ => PropsNotAnObjectSnafu {}.fail(),
+    }
+}
+
+/// A value to set at a dotted path in the properties, as given with `--set path=value` and
+/// friends.
+#[derive(Clone, Debug)]
+pub struct Assignment {
+    pub path: String,
+    pub value: AssignmentValue,
+}
+
+/// An enumeration of the ways the value of an assignment can be given.
+#[derive(Clone, Debug)]
+pub enum AssignmentValue {
+    /// The value is used as a string, as given with `--set`.
+    String(String),
+    /// The value is parsed as JSON, as given with `--set-json`.
+    Json(String),
+    /// The value is the contents of the file at this path, as given with `--set-file`.
+    File(String),
+    /// The value is the properties loaded from the file at this path, as given with `--context`.
+    Props(String),
+}
+
+impl Assignment {
+    /// Parse a `path=value` assignment, using the given kind of value.
+    pub fn parse(raw: &str, kind: fn(String) -> AssignmentValue) -> Result<Assignment> {
+        match raw.split_once('=') {
+            Some((path, value)) if !path.is_empty() => Ok(Assignment {
+                path: path.to_owned(),
+                value: kind(value.to_owned()),
+            }),
+            _ => InvalidAssignmentSnafu { assignment: raw }.fail(),
+        }
+    }
+
+    /// Set the value of this assignment in the properties, creating any missing objects on the way
+    /// and replacing any values that are in the way.
+    ///
+    /// It fails if the value is meant to be JSON but is not valid.
+    /// It fails if the value is meant to be read from a file that could not be read.
+    /// It fails if the value is meant to be properties that are not valid in their format.
+    pub fn apply(&self, props: &mut Json) -> Result<()> {
+        let value = match &self.value {
+            AssignmentValue::String(value) => Json::String(value.clone()),
+            AssignmentValue::Json(value) => serde_json::from_str(value)
+                .context(AssignmentInvalidJsonSnafu { path: &self.path })?,
+            AssignmentValue::File(file) => Json::String(
+                fs::read_to_string(file).context(AssignmentFileUnreadableSnafu { path: file })?,
+            ),
+            AssignmentValue::Props(file) => load_props_file(Path::new(file), None)?,
+        };
+        set_path(props, &self.path, value);
+        Ok(())
+    }
+}
+
+/// Set a value at a dotted path, such as `server.ports.0`, in the properties. Segments address
+/// keys of objects, or indexes of arrays where the index exists, and anything else in the way is
+/// replaced with an object.
+pub fn set_path(props: &mut Json, path: &str, value: Json) {
+    let mut current = props;
+    for segment in path.split('.') {
+        current = child_mut(current, segment);
+    }
+    *current = value;
+}
+
+/// Get the value at a dotted path in the properties, addressed in the same way as in `set_path`,
+/// if there is one.
+pub fn get_path<'a>(props: &'a Json, path: &str) -> Option<&'a Json> {
+    path.split('.')
+        .try_fold(props, |current, segment| match current {
+            Json::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            Json::Object(map) => map.get(segment),
+            _ => None,
+        })
+}
+
+/// Get the child of a value for a segment of a dotted path, creating it as described in `set_path`
+/// if it does not exist.
+fn child_mut<'a>(parent: &'a mut Json, segment: &str) -> &'a mut Json {
+    let index = match parent {
+        Json::Array(items) => segment
+            .parse::<usize>()
+            .ok()
+            .filter(|&index| index < items.len()),
+        _ => None,
+    };
+
+    match (parent, index) {
+        (Json::Array(items), Some(index)) => &mut items[index],
+        (Json::Object(map), _) => map.entry(segment).or_insert(Json::Null),
+        (other, _) => {
+            *other = Json::Object(Map::new());
+            child_mut(other, segment)
+        }
+    }
+}
+
+/// Parse CSV into an object whose `rows` are an array of objects, one per record, keyed by the
+/// values of the header record.
+fn parse_csv(raw: &[u8]) -> Result<Json> {
+    let mut reader = csv::Reader::from_reader(raw);
+    let headers = reader.headers().context(PropsInvalidCsvSnafu {})?.clone();
+
+    let rows = reader
+        .records()
+        .map(|record| {
+            let record = record.context(PropsInvalidCsvSnafu {})?;
+            let row = headers
+                .iter()
+                .zip(record.iter())
+                .map(|(header, value)| (header.to_owned(), Json::String(value.to_owned())))
+                .collect();
+            Ok(Json::Object(row))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(serde_json::json!({ "rows": Json::Array(rows) }))
+}
+
+/// Parse INI into an object, with the properties outside of any section at the top level and each
+/// section becoming a nested object of its properties.
+fn parse_ini(raw: &str) -> Result<Json> {
+    let ini = Ini::load_from_str(raw).context(PropsInvalidIniSnafu {})?;
+    let mut props = Map::new();
+
+    for (section, properties) in ini.iter() {
+        let values = properties
+            .iter()
+            .map(|(key, value)| (key.to_owned(), Json::String(value.to_owned())));
+
+        match section {
+            None => props.extend(values),
+            Some(section) => {
+                let entry = props
+                    .entry(section)
+                    .or_insert_with(|| Json::Object(Map::new()));
+                if let Json::Object(entry) = entry {
+                    entry.extend(values);
+                }
+            }
+        }
+    }
+
+    Ok(Json::Object(props))
+}
+
+/// Parse XML into an object holding the root element under its name.
+///
+/// Each element becomes an object with its attributes under their names prefixed with `@`, its
+/// child elements under their names (as an array when a name repeats) and its text under `#text`.
+/// Elements with nothing but text become plain strings instead.
+fn parse_xml(raw: &[u8]) -> quick_xml::Result<Json> {
+    let mut reader = quick_xml::Reader::from_reader(raw);
+    reader.config_mut().trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut root = Map::new();
+    let mut open: Vec<(String, Map<String, Json>)> = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => open.push(xml_element(&start)?),
+            Event::Empty(start) => {
+                let (name, element) = xml_element(&start)?;
+                let parent = open.last_mut().map(|(_, map)| map).unwrap_or(&mut root);
+                xml_insert(parent, name, xml_value(element));
+            }
+            Event::End(_) => {
+                if let Some((name, element)) = open.pop() {
+                    let parent = open.last_mut().map(|(_, map)| map).unwrap_or(&mut root);
+                    xml_insert(parent, name, xml_value(element));
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape()?.into_owned();
+                if let Some((_, element)) = open.last_mut() {
+                    xml_append_text(element, &text);
+                }
+            }
+            Event::CData(data) => {
+                let text = String::from_utf8_lossy(&data).into_owned();
+                if let Some((_, element)) = open.last_mut() {
+                    xml_append_text(element, &text);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(Json::Object(root))
+}
+
+/// Start an XML element, returning its name and an object holding its attributes.
+fn xml_element(start: &BytesStart) -> quick_xml::Result<(String, Map<String, Json>)> {
+    let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+    let mut element = Map::new();
+
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        let key = format!("@{}", String::from_utf8_lossy(attribute.key.as_ref()));
+        let value = attribute.unescape_value()?.into_owned();
+        element.insert(key, Json::String(value));
+    }
+
+    Ok((name, element))
+}
+
+/// Append some text to the `#text` of an XML element.
+fn xml_append_text(element: &mut Map<String, Json>, text: &str) {
+    match element.get_mut("#text") {
+        Some(Json::String(existing)) => existing.push_str(text),
+        _ => {
+            element.insert("#text".to_owned(), Json::String(text.to_owned()));
+        }
+    }
+}
+
+/// Convert a finished XML element into its value, collapsing elements with only text to strings.
+fn xml_value(mut element: Map<String, Json>) -> Json {
+    if element.is_empty() {
+        return Json::String(String::new());
+    }
+    if element.len() == 1 {
+        if let Some(text) = element.remove("#text") {
+            return text;
+        }
+    }
+    Json::Object(element)
+}
+
+/// Insert a child under its name, turning the entry into an array when the name repeats.
+fn xml_insert(parent: &mut Map<String, Json>, name: String, value: Json) {
+    match parent.get_mut(&name) {
+        Some(Json::Array(existing)) => existing.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Json::Array(vec![first, value]);
+        }
+        None => {
+            parent.insert(name, value);
+        }
+    }
+}
+
+/// Find the one-based line and column of the given byte offset into some text.
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeSet;
+
+use handlebars::template::{Parameter, Template, TemplateElement};
+use handlebars::Handlebars;
+use log::warn;
+use serde_json::Value as Json;
+
+/// The partials a template includes, and those they include in turn, as a tree.
+pub struct Tree {
+    name: String,
+    kind: Node,
+    children: Vec<Tree>,
+}
+
+/// What is known about a partial in the tree beyond its name.
+#[derive(PartialEq)]
+enum Node {
+    /// The partial is registered, and the partials it includes are beneath it.
+    Found,
+    /// The partial is not registered, so nothing beneath it is known.
+    Missing,
+    /// The partial is already being included further up the tree, so it is not walked again.
+    Cycle,
+}
+
+impl Tree {
+    /// The tree of the partials the template with the given name includes from the registry,
+    /// warning about every partial which ends up including itself.
+    pub fn of(handlebars: &Handlebars, name: &str) -> Tree {
+        tree(handlebars, name, &mut Vec::new())
+    }
+
+    /// The tree with one partial on each line, indented beneath the one including it.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        self.write_text(&mut text, 0);
+        text
+    }
+
+    fn write_text(&self, text: &mut String, depth: usize) {
+        let note = match self.kind {
+            Node::Found => "",
+            Node::Missing => " (missing)",
+            Node::Cycle => " (cycle)",
+        };
+        text.push_str(&format!("{}{}{}\n", "  ".repeat(depth), self.name, note));
+        for child in &self.children {
+            child.write_text(text, depth + 1);
+        }
+    }
+
+    /// The tree as a graph in the DOT language of Graphviz, with an edge from each template to
+    /// every partial it includes, and partials which are not registered dashed.
+    pub fn to_dot(&self) -> String {
+        let mut missing = BTreeSet::new();
+        let mut edges = BTreeSet::new();
+        self.collect(&mut missing, &mut edges);
+
+        let mut dot = String::from("digraph partials {\n");
+        for name in missing {
+            dot.push_str(&format!("  {:?} [style=dashed];\n", name));
+        }
+        for (from, to) in edges {
+            dot.push_str(&format!("  {:?} -> {:?};\n", from, to));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn collect<'a>(
+        &'a self,
+        missing: &mut BTreeSet<&'a str>,
+        edges: &mut BTreeSet<(&'a str, &'a str)>,
+    ) {
+        if self.kind == Node::Missing {
+            missing.insert(&self.name);
+        }
+        for child in &self.children {
+            edges.insert((&self.name, &child.name));
+            child.collect(missing, edges);
+        }
+    }
+}
+
+/// The tree of the partials the template includes, given the names of the templates including it.
+fn tree(handlebars: &Handlebars, name: &str, including: &mut Vec<String>) -> Tree {
+    let node = |kind| Tree {
+        name: name.to_owned(),
+        kind,
+        children: Vec::new(),
+    };
+    if including.iter().any(|including| including == name) {
+        warn!(
+            "Partial '{}' includes itself: {} > {}",
+            name,
+            including.join(" > "),
+            name
+        );
+        return node(Node::Cycle);
+    }
+    let template = match handlebars.get_template(name) {
+        Some(template) => template,
+        None => return node(Node::Missing),
+    };
+
+    let mut names = Vec::new();
+    let mut inline = Vec::new();
+    partials(template, &mut names, &mut inline);
+    // A partial block renders its own contents instead of a partial which is not there, as the
+    // blocks of layouts do.
+    names.retain(|(name, block)| {
+        let fallback = *block && handlebars.get_template(name).is_none();
+        !inline.contains(name) && !fallback
+    });
+    including.push(name.to_owned());
+    let children = names
+        .iter()
+        .map(|(partial, _)| tree(handlebars, partial, including))
+        .collect();
+    including.pop();
+    Tree {
+        children,
+        ..node(Node::Found)
+    }
+}
+
+/// Add the names of the partials the template includes, including those inside its blocks, in the
+/// order they first appear and with whether they are included as partial blocks, along with the
+/// names of those it defines itself with `{{#*inline}}`.
+///
+/// Partials whose names are only known as the template renders, and the @partial-block of a partial
+/// block, are left out.
+fn partials(template: &Template, names: &mut Vec<(String, bool)>, inline: &mut Vec<String>) {
+    for element in &template.elements {
+        match element {
+            TemplateElement::HelperBlock(helper) => {
+                for template in helper.template.iter().chain(&helper.inverse) {
+                    partials(template, names, inline);
+                }
+            }
+            TemplateElement::DecoratorBlock(decorator) => {
+                if decorator.name.as_name() == Some("inline") {
+                    if let Some(Parameter::Literal(Json::String(name))) = decorator.params.first() {
+                        inline.push(name.clone());
+                    }
+                }
+                if let Some(template) = &decorator.template {
+                    partials(template, names, inline);
+                }
+            }
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                if let Parameter::Name(name) = &partial.name {
+                    let block = partial.template.is_some();
+                    if !name.starts_with('@') && names.iter().all(|(other, _)| other != name) {
+                        names.push((name.clone(), block));
+                    }
+                }
+                if let Some(template) = &partial.template {
+                    partials(template, names, inline);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::error;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use handlebars::template::{Parameter, TemplateElement};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason,
+};
+use serde_json::Value as Json;
+
+use crate::instrument;
+use crate::Error;
+
+/// The names of the helpers keeping track of how deep partials are included, which no template can
+/// call.
+const INCLUDE_START_HELPER: &str = "--include-start";
+const INCLUDE_END_HELPER: &str = "--include-end";
+
+/// The error a render fails with when partials are included more deeply than the limit.
+#[derive(Debug)]
+struct DepthExceeded {
+    partial: String,
+    max: usize,
+}
+
+impl fmt::Display for DepthExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "partial '{}' was included more than {} levels deep",
+            self.partial, self.max
+        )
+    }
+}
+
+impl error::Error for DepthExceeded {}
+
+/// Rewrite every template registered so far to fail once partials, including those which include
+/// themselves, are included more than the given number of levels deep.
+pub fn limit(handlebars: &mut Handlebars, max: usize) {
+    let depth = Arc::new(AtomicUsize::new(0));
+    let helper = |start| IncludeHelper {
+        depth: Arc::clone(&depth),
+        max,
+        start,
+    };
+    handlebars.register_helper(INCLUDE_START_HELPER, Box::new(helper(true)));
+    handlebars.register_helper(INCLUDE_END_HELPER, Box::new(helper(false)));
+    instrument::instrument(handlebars, &|element, _| match element {
+        TemplateElement::PartialExpression(partial) | TemplateElement::PartialBlock(partial) => {
+            let name = partial.name.as_name().unwrap_or_default();
+            let name = vec![Parameter::Literal(Json::from(name))];
+            (
+                Some(instrument::call(INCLUDE_START_HELPER, name)),
+                Some(instrument::call(INCLUDE_END_HELPER, Vec::new())),
+            )
+        }
+        _ => (None, None),
+    });
+}
+
+/// The error for a render failing because partials were included too deeply, if that is why it
+/// failed.
+pub fn exceeded(err: &RenderError) -> Option<Error> {
+    let exceeded = match err.reason() {
+        RenderErrorReason::NestedError(err) => err.downcast_ref::<DepthExceeded>()?,
+        _ => return None,
+    };
+    Some(Error::IncludeDepthExceeded {
+        partial: exceeded.partial.clone(),
+        max: exceeded.max,
+    })
+}
+
+/// The helper put before a partial to note that it is being included, failing if that is too
+/// deep, or after it to note that it no longer is.
+struct IncludeHelper {
+    depth: Arc<AtomicUsize>,
+    max: usize,
+    start: bool,
+}
+
+impl HelperDef for IncludeHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        if !self.start {
+            self.depth.fetch_sub(1, Ordering::SeqCst);
+            return Ok(());
+        }
+        if self.depth.load(Ordering::SeqCst) >= self.max {
+            // The render ends here, so nothing else is being included any more.
+            self.depth.store(0, Ordering::SeqCst);
+            let partial = h.param(0).and_then(|param| param.value().as_str());
+            let exceeded = DepthExceeded {
+                partial: partial.unwrap_or_default().to_owned(),
+                max: self.max,
+            };
+            return Err(RenderErrorReason::NestedError(Box::new(exceeded)).into());
+        }
+        self.depth.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use std::borrow::Cow;
+
+use handlebars::template::TemplateElement;
+use handlebars::Handlebars;
+use serde_json::Value as Json;
+use snafu::ResultExt;
+
+use crate::data;
+use crate::{FrontMatterInvalidSnafu, FrontMatterNotAnObjectSnafu, Result};
+
+/// The line a block of front matter starts and ends with.
+const DELIMITER: &str = "---";
+
+/// Remove the block of YAML front matter between `---` lines at the very start of the template
+/// registered under the given name, if it has one, returning its values.
+///
+/// The template is rewritten rather than read again, so that the lines and columns of any errors in
+/// it stay the same. The front matter cannot contain expressions such as `{{name}}`, which would
+/// end the text it is found in early.
+///
+/// It fails if the front matter is not valid YAML or not a mapping.
+pub fn take(handlebars: &mut Handlebars, name: &str) -> Result<Option<Json>> {
+    let mut template = match handlebars.get_template(name) {
+        Some(template) => template.clone(),
+        None => return Ok(None),
+    };
+    let (front_matter, rest) = match template.elements.first() {
+        Some(TemplateElement::RawString(raw)) => match split(raw) {
+            Some((front_matter, rest)) => (front_matter.to_owned(), rest.to_owned()),
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    let values = match serde_yaml::from_str::<Json>(&front_matter)
+        .context(FrontMatterInvalidSnafu { name })?
+    {
+        Json::Null => Json::Object(Default::default()),
+        Json::Object(values) => Json::Object(values),
+        _ => return FrontMatterNotAnObjectSnafu { name }.fail(),
+    };
+    template.elements[0] = TemplateElement::RawString(rest);
+    handlebars.register_template(name, template);
+    Ok(Some(values))
+}
+
+/// The properties with the values of front matter merged beneath them, so that the properties
+/// take precedence.
+pub fn beneath<'a>(front_matter: Option<&Json>, props: &'a Json) -> Cow<'a, Json> {
+    match front_matter {
+        Some(front_matter) => {
+            let mut merged = front_matter.clone();
+            data::deep_merge(&mut merged, props.clone());
+            Cow::Owned(merged)
+        }
+        None => Cow::Borrowed(props),
+    }
+}
+
+/// Split the start of a template into the YAML of its front matter and what comes after the line
+/// ending it, if it starts with front matter.
+fn split(raw: &str) -> Option<(&str, &str)> {
+    let rest = raw.strip_prefix(DELIMITER)?;
+    let rest = rest
+        .strip_prefix('\n')
+        .or_else(|| rest.strip_prefix("\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == DELIMITER {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
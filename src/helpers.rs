@@ -0,0 +1,1016 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    ScopedJson,
+};
+
+use self::digest::Algorithm;
+use self::markup::Markup;
+use self::math::{Comparison, Operation};
+use self::quoting::{Quoting, Sql};
+use self::random::Source;
+use self::serialize::Syntax;
+
+mod case;
+mod collections;
+mod digest;
+mod encoding;
+mod human;
+mod ids;
+mod locale;
+mod markdown;
+mod markup;
+mod math;
+mod network;
+mod paths;
+mod patterns;
+mod quoting;
+mod radix;
+mod random;
+mod serialize;
+mod strings;
+mod style;
+mod time;
+mod versions;
+
+/// A function creating a helper to register.
+type HelperFn = fn() -> Box<dyn HelperDef + Send + Sync>;
+
+/// A function creating a helper to register which draws on the random values of the registry.
+type RandomFn = fn(Source) -> Box<dyn HelperDef + Send + Sync>;
+
+/// A function creating a helper to register which colours its text only if colour is enabled.
+type ColouredFn = fn(bool) -> Box<dyn HelperDef + Send + Sync>;
+
+/// An enumeration of the ways helpers are registered.
+enum Registration {
+    /// The engine registers the helper itself, or templates are rewritten into other elements for
+    /// it.
+    Builtin,
+    /// The helper the function creates is registered.
+    Function(HelperFn),
+    /// The helper the function creates from the random values of the registry is registered.
+    Random(RandomFn),
+    /// The helper the function creates for whether colour is enabled is registered.
+    Coloured(ColouredFn),
+}
+
+/// A helper which can be called inside templates, along with how to call it and what it does.
+pub struct Definition {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub description: &'static str,
+    registration: Registration,
+}
+
+/// Every helper this program makes available, in the order they are listed by `helpers`.
+pub const HELPERS: &[Definition] = &[
+    builtin(
+        "if",
+        "{{#if value}}...{{else}}...{{/if}}",
+        "Renders the block if the value is truthy, or the else block otherwise.",
+    ),
+    builtin(
+        "unless",
+        "{{#unless value}}...{{else}}...{{/unless}}",
+        "Renders the block if the value is falsy, or the else block otherwise.",
+    ),
+    builtin(
+        "each",
+        "{{#each value as |item key|}}...{{else}}...{{/each}}",
+        "Renders the block for each element of an array or entry of an object, with @index, @key, \
+         @first and @last, or the else block if there are none.",
+    ),
+    builtin(
+        "with",
+        "{{#with value as |item|}}...{{else}}...{{/with}}",
+        "Renders the block with the value as the context, or the else block if it is empty.",
+    ),
+    builtin(
+        "lookup",
+        "{{lookup value key}}",
+        "The element of an array or entry of an object at a key only known as the template renders.",
+    ),
+    builtin(
+        "raw",
+        "{{{{raw}}}}...{{{{/raw}}}}",
+        "Renders the block as it is, without expanding anything inside it.",
+    ),
+    builtin(
+        "log",
+        "{{log value... level=\"info\"}}",
+        "Logs the values at the given level, which is shown with --verbose.",
+    ),
+    builtin(
+        "extend",
+        "{{#extend \"layout\"}}{{#block \"name\"}}...{{/block}}{{/extend}}",
+        "Renders the layout partial with each of its blocks replaced by the block of the same name \
+         inside, if there is one.",
+    ),
+    builtin(
+        "block",
+        "{{#block \"name\"}}...{{/block}}",
+        "In a layout, a block which the templates extending it can replace, rendering its own \
+         contents if they do not.",
+    ),
+    helper(
+        "eq",
+        "(eq a b)",
+        "Whether the values are equal.",
+        || Box::new(math::Compare(Comparison::Eq)),
+    ),
+    helper(
+        "ne",
+        "(ne a b)",
+        "Whether the values are not equal.",
+        || Box::new(math::Compare(Comparison::Ne)),
+    ),
+    helper(
+        "gt",
+        "(gt a b)",
+        "Whether the first number or string is greater than the second.",
+        || Box::new(math::Compare(Comparison::Gt)),
+    ),
+    helper(
+        "gte",
+        "(gte a b)",
+        "Whether the first number or string is greater than or equal to the second.",
+        || Box::new(math::Compare(Comparison::Gte)),
+    ),
+    helper(
+        "lt",
+        "(lt a b)",
+        "Whether the first number or string is less than the second.",
+        || Box::new(math::Compare(Comparison::Lt)),
+    ),
+    helper(
+        "lte",
+        "(lte a b)",
+        "Whether the first number or string is less than or equal to the second.",
+        || Box::new(math::Compare(Comparison::Lte)),
+    ),
+    builtin("and", "(and a b...)", "Whether every value is truthy."),
+    builtin("or", "(or a b...)", "Whether any value is truthy."),
+    builtin("not", "(not value)", "Whether the value is falsy."),
+    helper(
+        "add",
+        "(add a b)",
+        "The sum of the numbers, such as for counting from one with (add @index 1).",
+        || Box::new(math::Arithmetic(Operation::Add)),
+    ),
+    helper(
+        "sub",
+        "(sub a b)",
+        "The difference of the numbers.",
+        || Box::new(math::Arithmetic(Operation::Sub)),
+    ),
+    helper(
+        "mul",
+        "(mul a b)",
+        "The product of the numbers.",
+        || Box::new(math::Arithmetic(Operation::Mul)),
+    ),
+    helper(
+        "div",
+        "(div a b)",
+        "The quotient of the numbers, which is an integer only if they divide exactly.",
+        || Box::new(math::Arithmetic(Operation::Div)),
+    ),
+    helper(
+        "mod",
+        "(mod a b)",
+        "The remainder of dividing the numbers, such as for alternating rows with (mod @index 2).",
+        || Box::new(math::Arithmetic(Operation::Mod)),
+    ),
+    helper(
+        "round",
+        "(round value places=0)",
+        "The number rounded to the given number of decimal places.",
+        || Box::new(math::round),
+    ),
+    helper(
+        "num_format",
+        "{{num_format value locale=\"en-US\" decimals=N}}",
+        "The number with the separators between thousands and before the decimals of the locale, \
+         such as 1.234,50 for {{num_format 1234.5 locale=\"de-DE\" decimals=2}}, rounded to the \
+         number of decimals if it is given, at most 20.",
+        || Box::new(locale::NumFormat),
+    ),
+    helper(
+        "hex",
+        "{{hex value width=0 prefix=false}}",
+        "The integer in hexadecimal, with zeros before it to make it at least the width and 0x if \
+         prefix is true, such as 0x00ff for {{hex 255 width=4 prefix=true}}.",
+        || Box::new(radix::hex),
+    ),
+    helper(
+        "from_hex",
+        "(from_hex value)",
+        "The integer a string of hexadecimal gives, with or without 0x before it.",
+        || Box::new(radix::from_hex),
+    ),
+    helper(
+        "to_radix",
+        "{{to_radix value base width=0}}",
+        "The integer in a base from 2 to 36, with zeros before it to make it at least the width, \
+         such as 1010 for {{to_radix 10 2}}.",
+        || Box::new(radix::to_radix),
+    ),
+    helper(
+        "from_radix",
+        "(from_radix value base)",
+        "The integer a string gives in a base from 2 to 36, where _ can separate the digits.",
+        || Box::new(radix::from_radix),
+    ),
+    helper(
+        "hex_bytes",
+        "{{hex_bytes value sep=\" \"}}",
+        "The bytes of an array of numbers from 0 to 255, or of a string in UTF-8, each as two \
+         hexadecimal digits with the separator between them, such as de ad be ef.",
+        || Box::new(radix::hex_bytes),
+    ),
+    helper(
+        "plural",
+        "{{plural count singular plural}}",
+        "The singular if the count is one, or the plural otherwise, such as {{count}} {{plural \
+         count \"item\" \"items\"}}.",
+        || Box::new(locale::plural),
+    ),
+    helper(
+        "plural_select",
+        "{{plural_select count locale=\"en-US\" zero= one= two= few= many= other=}}",
+        "The form for the plural category the count is in by the rules of the locale, or the other \
+         form if it is not given, with # in it replaced by the count, for languages with more \
+         than two forms such as {{plural_select 3 locale=\"pl\" one=\"# plik\" few=\"# pliki\" \
+         many=\"# plików\" other=\"# pliku\"}}.",
+        || Box::new(locale::PluralSelect),
+    ),
+    builtin(
+        "len",
+        "(len value)",
+        "The number of elements of an array, entries of an object or characters of a string.",
+    ),
+    helper(
+        "snake_case",
+        "{{snake_case value}}",
+        "The string in snake_case, such as user_id for userId.",
+        || Box::new(case::snake_case),
+    ),
+    helper(
+        "camel_case",
+        "{{camel_case value}}",
+        "The string in camelCase, such as userId for user_id.",
+        || Box::new(case::camel_case),
+    ),
+    helper(
+        "kebab_case",
+        "{{kebab_case value}}",
+        "The string in kebab-case, such as user-id for userId.",
+        || Box::new(case::kebab_case),
+    ),
+    helper(
+        "pascal_case",
+        "{{pascal_case value}}",
+        "The string in PascalCase, such as UserId for user_id.",
+        || Box::new(case::pascal_case),
+    ),
+    helper(
+        "upper",
+        "{{upper value}}",
+        "The string in upper case.",
+        || Box::new(case::upper),
+    ),
+    helper(
+        "lower",
+        "{{lower value}}",
+        "The string in lower case.",
+        || Box::new(case::lower),
+    ),
+    helper(
+        "slug",
+        "{{slug value}}",
+        "The string in lower case without diacritics and with dashes in place of anything other \
+         than letters and digits, for file names, URLs and anchors, such as creme-brulee-recipe \
+         for Crème Brûlée: Recipe.",
+        || Box::new(strings::slug),
+    ),
+    helper(
+        "truncate",
+        "{{truncate value width}}",
+        "The first characters of the string, up to the given number of them.",
+        || Box::new(strings::truncate),
+    ),
+    helper(
+        "ellipsis",
+        "{{ellipsis value width}}",
+        "The string shortened to the given number of characters with … at the end, if it is \
+         longer than that.",
+        || Box::new(strings::ellipsis),
+    ),
+    helper(
+        "wordwrap",
+        "{{wordwrap value width}}",
+        "The string with its lines broken between words so that none is longer than the given \
+         number of characters, for fixed-width text such as emails and commit messages.",
+        || Box::new(strings::wordwrap),
+    ),
+    helper(
+        "join",
+        "{{join values separator}}",
+        "The elements of an array with the separator between each of them.",
+        || Box::new(strings::join),
+    ),
+    helper(
+        "split",
+        "(split value separator)",
+        "The array of the parts of the string between each separator.",
+        || Box::new(strings::split),
+    ),
+    helper(
+        "sort_by",
+        "(sort_by values key desc=false)",
+        "The array sorted by the value at the dotted key of each element, such as \
+         {{#each (sort_by items \"price\" desc=true)}}, keeping elements with the same value in \
+         the order they were in.",
+        || Box::new(collections::sort_by),
+    ),
+    helper(
+        "group_by",
+        "(group_by values key)",
+        "The object of arrays of the elements with the same value at the dotted key, by that \
+         value, to be used with {{#each}} and @key.",
+        || Box::new(collections::group_by),
+    ),
+    helper(
+        "unique",
+        "(unique values key)",
+        "The array without the elements equal to one before them, or with the same value at the \
+         dotted key if it is given.",
+        || Box::new(collections::unique),
+    ),
+    helper(
+        "filter",
+        "(filter values key value)",
+        "The array of the elements whose value at the dotted key is equal to the value, or is \
+         truthy if no value is given.",
+        || Box::new(collections::filter),
+    ),
+    helper(
+        "slice",
+        "(slice values start end)",
+        "The elements of the array from the start up to but not including the end, or to the end \
+         of the array if it is not given, counting from the end for negative indexes.",
+        || Box::new(collections::slice),
+    ),
+    helper(
+        "default",
+        "{{default value fallback}}",
+        "The value, or the fallback if it is missing, null or an empty string, array or object, \
+         even in strict mode.",
+        || Box::new(collections::default),
+    ),
+    helper(
+        "coalesce",
+        "{{coalesce value...}}",
+        "The first of the values which is not missing, null or an empty string, array or object, \
+         or null if there is none, even in strict mode.",
+        || Box::new(collections::coalesce),
+    ),
+    helper(
+        "trim",
+        "{{trim value}}",
+        "The string without whitespace at either end.",
+        || Box::new(strings::trim),
+    ),
+    helper(
+        "pad_left",
+        "{{pad_left value width padding}}",
+        "The value with the padding, or spaces if it is not given, before it to make it the given \
+         number of characters long, such as 007 for {{pad_left 7 3 \"0\"}}.",
+        || Box::new(strings::pad_left),
+    ),
+    helper(
+        "pad_right",
+        "{{pad_right value width padding}}",
+        "The value with the padding, or spaces if it is not given, after it to make it the given \
+         number of characters long.",
+        || Box::new(strings::pad_right),
+    ),
+    helper(
+        "replace",
+        "{{replace value from to}}",
+        "The string with every occurrence of one string in it replaced by another.",
+        || Box::new(strings::replace),
+    ),
+    helper(
+        "indent",
+        "{{indent value width}}, {{#indent width}}...{{/indent}}",
+        "The string, or the block, with the given number of spaces before each of its lines which \
+         are not blank, for embedding text in YAML or nested code.",
+        || Box::new(strings::Indent),
+    ),
+    helper(
+        "regex_match",
+        "(regex_match value pattern)",
+        "Whether the string matches the regular expression anywhere.",
+        || Box::new(patterns::regex_match),
+    ),
+    helper(
+        "regex_replace",
+        "{{regex_replace value pattern replacement}}",
+        "The string with every match of the regular expression replaced, where $1 or $name in the \
+         replacement is what the group of that number or name matched.",
+        || Box::new(patterns::regex_replace),
+    ),
+    helper(
+        "regex_capture",
+        "(regex_capture value pattern)",
+        "The array of what the first match of the regular expression and each of its groups \
+         matched, or null if it does not match.",
+        || Box::new(patterns::regex_capture),
+    ),
+    helper(
+        "if_regex",
+        "{{#if_regex value pattern}}...{{else}}...{{/if_regex}}",
+        "Renders the block if the string matches the regular expression, or the else block \
+         otherwise.",
+        || Box::new(patterns::IfRegex),
+    ),
+    helper(
+        "toJson",
+        "{{toJson value pretty=false indent=0}}",
+        "The value as JSON, on a single line unless it is pretty, without escaping it. Every line \
+         after the first is indented by the given number of spaces, to line up with where the \
+         helper is.",
+        || Box::new(serialize::To(Syntax::Json)),
+    ),
+    helper(
+        "toYaml",
+        "{{toYaml value indent=0}}",
+        "The value as YAML, without escaping it. Every line after the first is indented by the \
+         given number of spaces, to line up with where the helper is.",
+        || Box::new(serialize::To(Syntax::Yaml)),
+    ),
+    helper(
+        "toToml",
+        "{{toToml value indent=0}}",
+        "The object as TOML, without escaping it. Every line after the first is indented by the \
+         given number of spaces, to line up with where the helper is.",
+        || Box::new(serialize::To(Syntax::Toml)),
+    ),
+    helper(
+        "fromJson",
+        "(fromJson value)",
+        "The value held by a string of JSON, such as one embedded in the properties, to be used by \
+         other helpers as in {{#each (fromJson payload)}}.",
+        || Box::new(serialize::Parse(Syntax::Json)),
+    ),
+    helper(
+        "fromYaml",
+        "(fromYaml value)",
+        "The value held by a string of YAML.",
+        || Box::new(serialize::Parse(Syntax::Yaml)),
+    ),
+    helper(
+        "fromToml",
+        "(fromToml value)",
+        "The value held by a string of TOML.",
+        || Box::new(serialize::Parse(Syntax::Toml)),
+    ),
+    helper(
+        "b64enc",
+        "{{b64enc value}}",
+        "The string encoded as base64, such as for Kubernetes secrets and basic authentication.",
+        || Box::new(encoding::b64enc),
+    ),
+    helper(
+        "b64dec",
+        "{{b64dec value}}",
+        "The text a string of base64 encodes.",
+        || Box::new(encoding::b64dec),
+    ),
+    helper(
+        "b64enc_url",
+        "{{b64enc_url value}}",
+        "The string encoded as URL-safe base64 without padding, for URLs and file names.",
+        || Box::new(encoding::b64enc_url),
+    ),
+    helper(
+        "b64dec_url",
+        "{{b64dec_url value}}",
+        "The text a string of URL-safe base64 without padding encodes.",
+        || Box::new(encoding::b64dec_url),
+    ),
+    helper(
+        "jwt_claims",
+        "(jwt_claims token)",
+        "The object of the claims in the payload of a JSON Web Token, such as \
+         {{#with (jwt_claims token)}}{{email}}{{/with}}. The signature is NOT verified, so this is \
+         only for looking inside tokens, never for trusting them.",
+        || Box::new(encoding::jwt_claims),
+    ),
+    helper(
+        "urlencode",
+        "{{urlencode value}}",
+        "The string percent-encoded for a component of a URL, such as a query parameter or a \
+         segment of a path, so that a&b=c becomes a%26b%3Dc.",
+        || Box::new(encoding::urlencode),
+    ),
+    helper(
+        "urldecode",
+        "{{urldecode value}}",
+        "The text a percent-encoded string encodes.",
+        || Box::new(encoding::urldecode),
+    ),
+    helper(
+        "url_join",
+        "{{url_join base path...}}",
+        "The URL with the paths appended, with a single / between each of them whatever they start \
+         and end with, such as https://example.com/docs/guide for {{url_join \"https://example.com/\" \
+         \"/docs\" \"guide\"}}.",
+        || Box::new(encoding::url_join),
+    ),
+    helper(
+        "shell_quote",
+        "{{shell_quote value}}",
+        "The value quoted as a single word for a POSIX shell, without escaping it, for generated \
+         shell scripts. Words which need no quoting are left as they are.",
+        || Box::new(quoting::Quote(Quoting::Posix)),
+    ),
+    helper(
+        "ps_quote",
+        "{{ps_quote value}}",
+        "The value quoted as a single-quoted string for PowerShell, without escaping it.",
+        || Box::new(quoting::Quote(Quoting::PowerShell)),
+    ),
+    helper(
+        "sql_str",
+        "{{sql_str value dialect=\"ansi\"}}",
+        "The value quoted as a string literal of SQL, or NULL if it is null, without escaping it, \
+         for seed data and migrations. The dialect is ansi, postgres, mysql, sqlite or mssql.",
+        || Box::new(quoting::SqlQuote(Sql::Literal)),
+    ),
+    helper(
+        "sql_ident",
+        "{{sql_ident name dialect=\"ansi\"}}",
+        "The name quoted as an identifier of SQL, such as a table or column, without escaping it. \
+         The dialect is ansi, postgres, mysql, sqlite or mssql.",
+        || Box::new(quoting::SqlQuote(Sql::Identifier)),
+    ),
+    helper(
+        "xml_escape",
+        "{{xml_escape value}}",
+        "The value with the characters special to XML and HTML replaced with entities, whatever \
+         --escape says, for escaping particular values of templates rendered with --no-escape.",
+        || Box::new(markup::MarkupEscape(Markup::Text)),
+    ),
+    helper(
+        "attr_escape",
+        "{{attr_escape value}}",
+        "The value escaped as xml_escape does, with tabs and line breaks also replaced with \
+         character references so that they are kept inside an attribute value.",
+        || Box::new(markup::MarkupEscape(Markup::Attribute)),
+    ),
+    helper(
+        "markdown",
+        "{{markdown value html=true}}, {{#markdown}}...{{/markdown}}",
+        "The string, or the block, of Markdown rendered as HTML without escaping it, with tables, \
+         strikethrough, task lists and footnotes. HTML inside the Markdown is kept as it is unless \
+         html is false, which escapes it and keeps links and images from pointing to schemes other \
+         than http, https and mailto, such as javascript:, for Markdown which is not trusted.",
+        || Box::new(markdown::Markdown),
+    ),
+    coloured(
+        "bold",
+        "{{bold value}}, {{#bold}}...{{/bold}}",
+        "The text, or the block, in bold when colour is enabled by --color, which by default it \
+         is when stdout is a terminal and NO_COLOR is not set.",
+        |enabled| Box::new(style::Paint {
+            style: Some("bold"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "dim",
+        "{{dim value}}, {{#dim}}...{{/dim}}",
+        "The text, or the block, dimmed when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("dim"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "italic",
+        "{{italic value}}, {{#italic}}...{{/italic}}",
+        "The text, or the block, in italics when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("italic"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "underline",
+        "{{underline value}}, {{#underline}}...{{/underline}}",
+        "The text, or the block, underlined when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("underline"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "red",
+        "{{red value}}, {{#red}}...{{/red}}",
+        "The text, or the block, in red when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("red"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "green",
+        "{{green value}}, {{#green}}...{{/green}}",
+        "The text, or the block, in green when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("green"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "yellow",
+        "{{yellow value}}, {{#yellow}}...{{/yellow}}",
+        "The text, or the block, in yellow when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("yellow"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "blue",
+        "{{blue value}}, {{#blue}}...{{/blue}}",
+        "The text, or the block, in blue when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("blue"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "magenta",
+        "{{magenta value}}, {{#magenta}}...{{/magenta}}",
+        "The text, or the block, in magenta when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("magenta"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "cyan",
+        "{{cyan value}}, {{#cyan}}...{{/cyan}}",
+        "The text, or the block, in cyan when colour is enabled.",
+        |enabled| Box::new(style::Paint {
+            style: Some("cyan"),
+            enabled,
+        }),
+    ),
+    coloured(
+        "color",
+        "{{color value style}}, {{#color style}}...{{/color}}",
+        "The text, or the block, in a colour or style when colour is enabled, which is bold, dim, \
+         italic, underline, black, red, green, yellow, blue, magenta, cyan or white, or one of \
+         the colours with bright- before it such as bright-blue.",
+        |enabled| Box::new(style::Paint {
+            style: None,
+            enabled,
+        }),
+    ),
+    helper(
+        "sha256",
+        "{{sha256 value file=false}}",
+        "The SHA-256 digest of the string in hexadecimal, or of the contents of the file at that \
+         path if file is true, such as for cache-busting file names and checksums.",
+        || Box::new(digest::Digest(Algorithm::Sha256)),
+    ),
+    helper(
+        "sha1",
+        "{{sha1 value file=false}}",
+        "The SHA-1 digest of the string, or of the file at that path, in hexadecimal.",
+        || Box::new(digest::Digest(Algorithm::Sha1)),
+    ),
+    helper(
+        "md5",
+        "{{md5 value file=false}}",
+        "The MD5 digest of the string, or of the file at that path, in hexadecimal.",
+        || Box::new(digest::Digest(Algorithm::Md5)),
+    ),
+    helper(
+        "crc32",
+        "{{crc32 value file=false}}",
+        "The CRC-32 checksum of the string, or of the file at that path, in hexadecimal.",
+        || Box::new(digest::Digest(Algorithm::Crc32)),
+    ),
+    helper(
+        "uuid",
+        "{{uuid}}",
+        "A random version 4 UUID, which is different every time.",
+        || Box::new(ids::uuid),
+    ),
+    helper(
+        "uuidv5",
+        "{{uuidv5 namespace name}}",
+        "The version 5 UUID of the name in the namespace, which is dns, url, oid, x500 or a UUID, \
+         and is the same every time for stable identifiers derived from the properties.",
+        || Box::new(ids::uuidv5),
+    ),
+    helper(
+        "now",
+        "{{now format}}",
+        "The current local time in the strftime format, such as %Y-%m-%d, or in RFC 3339 format \
+         if none is given.",
+        || Box::new(time::now),
+    ),
+    helper(
+        "date_format",
+        "{{date_format value format}}",
+        "The date in the strftime format, where the date is a number of seconds since the Unix \
+         epoch or a string in RFC 3339 format, with or without its time and time zone.",
+        || Box::new(time::date_format),
+    ),
+    helper(
+        "date_add",
+        "{{date_add value duration}}",
+        "The date in RFC 3339 format after the duration, which is made of weeks (w), days (d), \
+         hours (h), minutes (m) and seconds (s) such as 3d or 1h30m, and goes back if it starts \
+         with -.",
+        || Box::new(time::date_add),
+    ),
+    helper(
+        "date_parse",
+        "{{date_parse value format}}",
+        "The date in RFC 3339 format which the string gives in the strftime format, taken to be in \
+         UTC if it has no time zone, for other helpers to use.",
+        || Box::new(time::date_parse),
+    ),
+    helper(
+        "filesize",
+        "{{filesize bytes}}",
+        "The number of bytes in the largest binary unit there is at least one of, such as 3.2 MiB.",
+        || Box::new(human::filesize),
+    ),
+    helper(
+        "duration",
+        "{{duration seconds}}",
+        "The number of seconds in days, hours, minutes and seconds, only showing the largest unit \
+         and the one after it, such as 2h 15m.",
+        || Box::new(human::duration),
+    ),
+    helper(
+        "ago",
+        "{{ago value}}",
+        "How long ago a date was, or how long until it is, such as 3 hours ago or in 2 days, \
+         where the date is as date_format takes it.",
+        || Box::new(human::ago),
+    ),
+    helper(
+        "semver_major",
+        "{{semver_major version}}",
+        "The major number of a semantic version, which may start with v as in v1.2.3.",
+        || Box::new(versions::semver_major),
+    ),
+    helper(
+        "semver_minor",
+        "{{semver_minor version}}",
+        "The minor number of a semantic version.",
+        || Box::new(versions::semver_minor),
+    ),
+    helper(
+        "semver_patch",
+        "{{semver_patch version}}",
+        "The patch number of a semantic version.",
+        || Box::new(versions::semver_patch),
+    ),
+    helper(
+        "semver_bump",
+        "{{semver_bump version part}}",
+        "The next semantic version for a release of the part, which is major, minor or patch, \
+         such as 1.3.0 for {{semver_bump \"1.2.3\" \"minor\"}}. A pre-release is released as the \
+         version it comes before if that is the next one.",
+        || Box::new(versions::semver_bump),
+    ),
+    helper(
+        "semver_matches",
+        "(semver_matches version requirement)",
+        "Whether a semantic version meets a requirement as Cargo writes them, such as ^1.2 or \
+         >=1.0, <2.",
+        || Box::new(versions::semver_matches),
+    ),
+    helper(
+        "cidr_contains",
+        "(cidr_contains cidr ip)",
+        "Whether the IPv4 or IPv6 network in CIDR notation, such as 10.0.0.0/8, contains the \
+         address.",
+        || Box::new(network::cidr_contains),
+    ),
+    helper(
+        "cidr_host",
+        "{{cidr_host cidr index}}",
+        "The address at the index in the network, counting from the network address itself, or \
+         from the end for negative indexes, such as 10.0.1.5 for {{cidr_host \"10.0.1.0/24\" 5}}.",
+        || Box::new(network::cidr_host),
+    ),
+    helper(
+        "ip_next",
+        "{{ip_next ip step}}",
+        "The address the step, or one if it is not given, after the address, or before it if the \
+         step is negative.",
+        || Box::new(network::ip_next),
+    ),
+    helper(
+        "dirname",
+        "{{dirname path}}",
+        "The path of the directory containing the path, or . if it has none.",
+        || Box::new(paths::dirname),
+    ),
+    helper(
+        "basename",
+        "{{basename path}}",
+        "The last component of the path, such as main.rs for src/main.rs.",
+        || Box::new(paths::basename),
+    ),
+    helper(
+        "ext",
+        "{{ext path}}",
+        "The extension of the path without the dot, such as gz for archive.tar.gz, or nothing if \
+         it has none.",
+        || Box::new(paths::ext),
+    ),
+    helper(
+        "path_join",
+        "{{path_join path...}}",
+        "The paths joined with the separator of the platform, starting again from any which is \
+         absolute.",
+        || Box::new(paths::path_join),
+    ),
+    helper(
+        "relpath",
+        "{{relpath from to}}",
+        "The path to the second path from the first, which is taken to be a directory even if it \
+         names a file, such as ../lib/util.h for {{relpath \"src\" \"lib/util.h\"}}, worked out \
+         without reading the file system.",
+        || Box::new(paths::relpath),
+    ),
+    random(
+        "random_int",
+        "{{random_int min max}}",
+        "A random integer from the first to the second, including both, which is the same on \
+         every run given the same --seed.",
+        |source| Box::new(random::RandomInt(source)),
+    ),
+    random(
+        "random_choice",
+        "{{random_choice values}}",
+        "A random element of an array, which is the same on every run given the same --seed.",
+        |source| Box::new(random::RandomChoice(source)),
+    ),
+    random(
+        "random_string",
+        "{{random_string length}}",
+        "A random string of letters and digits of the given length, which is the same on every \
+         run given the same --seed.",
+        |source| Box::new(random::RandomString(source)),
+    ),
+];
+
+/// The definition of a helper which is not registered as a function, because the engine provides
+/// it or because templates are rewritten for it.
+const fn builtin(
+    name: &'static str,
+    signature: &'static str,
+    description: &'static str,
+) -> Definition {
+    Definition {
+        name,
+        signature,
+        description,
+        registration: Registration::Builtin,
+    }
+}
+
+/// The definition of a helper registered as the one the function creates.
+const fn helper(
+    name: &'static str,
+    signature: &'static str,
+    description: &'static str,
+    helper: HelperFn,
+) -> Definition {
+    Definition {
+        name,
+        signature,
+        description,
+        registration: Registration::Function(helper),
+    }
+}
+
+/// The definition of a helper registered as the one the function creates from the random values of
+/// the registry.
+const fn random(
+    name: &'static str,
+    signature: &'static str,
+    description: &'static str,
+    helper: RandomFn,
+) -> Definition {
+    Definition {
+        name,
+        signature,
+        description,
+        registration: Registration::Random(helper),
+    }
+}
+
+/// The definition of a helper registered as the one the function creates for whether colour is
+/// enabled.
+const fn coloured(
+    name: &'static str,
+    signature: &'static str,
+    description: &'static str,
+    helper: ColouredFn,
+) -> Definition {
+    Definition {
+        name,
+        signature,
+        description,
+        registration: Registration::Coloured(helper),
+    }
+}
+
+/// Register every helper this program makes available which the engine does not register itself,
+/// with the random helpers sharing values seeded by the given seed, or from the operating system if
+/// none is given, and the styling helpers colouring their text only if colour is enabled.
+///
+/// A helper called with nothing, as in `{{name}}`, renders the property of the same name instead
+/// if there is one, so that properties are not hidden by helpers which happen to share their names.
+pub fn register(handlebars: &mut Handlebars, seed: Option<u64>, colour: bool) {
+    let source = Source::new(seed);
+    for definition in HELPERS {
+        let helper = match definition.registration {
+            Registration::Builtin => continue,
+            Registration::Function(helper) => helper(),
+            Registration::Random(helper) => helper(source.clone()),
+            Registration::Coloured(helper) => helper(colour),
+        };
+        handlebars.register_helper(definition.name, Box::new(PropertyFirst(helper)));
+    }
+}
+
+/// A helper which, when called with no parameters, hash or block, gives the property of its own
+/// name if there is one, and is the helper it wraps otherwise.
+struct PropertyFirst(Box<dyn HelperDef + Send + Sync>);
+
+impl PropertyFirst {
+    /// The property of the helper's name, if the helper was called with nothing and it is there.
+    fn property<'rc>(
+        h: &Helper<'rc>,
+        ctx: &'rc Context,
+        rc: &RenderContext<'_, 'rc>,
+    ) -> Result<Option<ScopedJson<'rc>>, RenderError> {
+        if !h.params().is_empty() || !h.hash().is_empty() || h.template().is_some() {
+            return Ok(None);
+        }
+        let value = rc.evaluate(ctx, h.name())?;
+        Ok((!value.is_missing()).then_some(value))
+    }
+}
+
+impl HelperDef for PropertyFirst {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        match Self::property(h, ctx, rc)? {
+            Some(value) => Ok(value),
+            None => self.0.call_inner(h, r, ctx, rc),
+        }
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        match Self::property(h, ctx, rc)? {
+            Some(value) => {
+                let rendered = value.render();
+                if rc.is_disable_escape() {
+                    out.write(&rendered)?;
+                } else {
+                    out.write(&r.get_escape_fn()(&rendered))?;
+                }
+                Ok(())
+            }
+            None => self.0.call(h, r, ctx, rc, out),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use handlebars::handlebars_helper;
+use heck::{ToKebabCase, ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
+
+handlebars_helper!(snake_case: |value: str| value.to_snake_case());
+handlebars_helper!(camel_case: |value: str| value.to_lower_camel_case());
+handlebars_helper!(kebab_case: |value: str| value.to_kebab_case());
+handlebars_helper!(pascal_case: |value: str| value.to_upper_camel_case());
+handlebars_helper!(upper: |value: str| value.to_uppercase());
+handlebars_helper!(lower: |value: str| value.to_lowercase());
@@ -0,0 +1,127 @@
+use std::cmp::Ordering;
+
+use handlebars::{handlebars_helper, JsonTruthy};
+use serde_json::{Map, Value as Json};
+
+use super::strings::text;
+use crate::data;
+
+handlebars_helper!(sort_by: |values: array, key: str, {desc: bool = false}| {
+    let mut sorted = values.clone();
+    sorted.sort_by(|a, b| {
+        let order = order(pluck(a, key), pluck(b, key));
+        if desc {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+    sorted
+});
+handlebars_helper!(group_by: |values: array, key: str| {
+    let mut groups = Map::new();
+    for value in values {
+        let group = groups
+            .entry(text(pluck(value, key)).into_owned())
+            .or_insert_with(|| Json::Array(Vec::new()));
+        if let Json::Array(group) = group {
+            group.push(value.clone());
+        }
+    }
+    groups
+});
+handlebars_helper!(unique: |values: array, *args| {
+    let key = args.get(1).and_then(|key| key.as_str());
+    let mut seen = Vec::new();
+    let mut kept = Vec::new();
+    for value in values {
+        let identity = key.map_or(value, |key| pluck(value, key));
+        if !seen.contains(&identity) {
+            seen.push(identity);
+            kept.push(value.clone());
+        }
+    }
+    kept
+});
+handlebars_helper!(filter: |values: array, key: str, *args| {
+    let expected = args.get(2);
+    values
+        .iter()
+        .filter(|value| {
+            let actual = pluck(value, key);
+            match expected {
+                Some(expected) => actual == *expected,
+                None => actual.is_truthy(false),
+            }
+        })
+        .cloned()
+        .collect::<Vec<_>>()
+});
+handlebars_helper!(slice: |values: array, start: i64, *args| {
+    let end = args.get(2).and_then(|end| end.as_i64());
+    let start = index(start, values.len());
+    let end = end.map_or(values.len(), |end| index(end, values.len()));
+    values[start..end.max(start)].to_vec()
+});
+
+// The parameters are taken as they are, so that those missing can be left out in strict mode too.
+handlebars_helper!(default: |*args| {
+    match args.first() {
+        Some(value) if !is_empty(value) => (*value).clone(),
+        _ => args.get(1).map_or(Json::Null, |fallback| (*fallback).clone()),
+    }
+});
+handlebars_helper!(coalesce: |*args| {
+    args.iter()
+        .find(|value| !is_empty(value))
+        .map_or(Json::Null, |value| (*value).clone())
+});
+
+/// Whether a value is missing, null, or an empty string, array or object.
+fn is_empty(value: &Json) -> bool {
+    match value {
+        Json::Null => true,
+        Json::String(value) => value.is_empty(),
+        Json::Array(elements) => elements.is_empty(),
+        Json::Object(entries) => entries.is_empty(),
+        _ => false,
+    }
+}
+
+/// The value at a dotted path such as `author.name` inside another, addressed as with `--set`, or
+/// null if there is none.
+fn pluck<'a>(value: &'a Json, key: &str) -> &'a Json {
+    data::get_path(value, key).unwrap_or(&Json::Null)
+}
+
+/// The order of two values, numbers by their value and strings lexicographically, with values of
+/// different types in the order null, booleans, numbers, strings, arrays and objects.
+fn order(a: &Json, b: &Json) -> Ordering {
+    let rank = |value: &Json| match value {
+        Json::Null => 0,
+        Json::Bool(_) => 1,
+        Json::Number(_) => 2,
+        Json::String(_) => 3,
+        Json::Array(_) => 4,
+        Json::Object(_) => 5,
+    };
+    match (a, b) {
+        (Json::Bool(a), Json::Bool(b)) => a.cmp(b),
+        (Json::Number(a), Json::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Json::String(a), Json::String(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// The position in an array of the given length an index refers to, counting from the end if it
+/// is negative and clamped to the bounds of the array.
+fn index(index: i64, len: usize) -> usize {
+    if index < 0 {
+        len.saturating_sub(index.unsigned_abs() as usize)
+    } else {
+        (index as usize).min(len)
+    }
+}
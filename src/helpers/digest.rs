@@ -0,0 +1,78 @@
+use std::fs;
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use md5::Md5;
+use serde_json::Value as Json;
+use sha1::Sha1;
+use sha2::{Digest as _, Sha256};
+
+/// An enumeration of the digests helpers can compute.
+#[derive(Clone, Copy)]
+pub enum Algorithm {
+    Sha256,
+    Sha1,
+    Md5,
+    Crc32,
+}
+
+impl Algorithm {
+    /// The name of the helper computing this digest.
+    fn helper(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha1 => "sha1",
+            Algorithm::Md5 => "md5",
+            Algorithm::Crc32 => "crc32",
+        }
+    }
+
+    /// This digest of the data in lower case hexadecimal.
+    fn hex(self, data: &[u8]) -> String {
+        let digest = match self {
+            Algorithm::Sha256 => Sha256::digest(data).to_vec(),
+            Algorithm::Sha1 => Sha1::digest(data).to_vec(),
+            Algorithm::Md5 => Md5::digest(data).to_vec(),
+            Algorithm::Crc32 => crc32fast::hash(data).to_be_bytes().to_vec(),
+        };
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
+
+/// The helper computing a digest of a string, or of the contents of the file at that path if
+/// `file=true` is given.
+pub struct Digest(pub Algorithm);
+
+impl HelperDef for Digest {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let helper = self.0.helper();
+        let value = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, 0))?;
+        let value = value
+            .value()
+            .as_str()
+            .ok_or(RenderErrorReason::InvalidParamType("string"))?;
+        let file = h
+            .hash_get("file")
+            .is_some_and(|file| file.value().as_bool() == Some(true));
+
+        let digest = if file {
+            let contents = fs::read(value).map_err(|err| {
+                RenderErrorReason::Other(format!("{} could not read '{}': {}", helper, value, err))
+            })?;
+            self.0.hex(&contents)
+        } else {
+            self.0.hex(value.as_bytes())
+        };
+        Ok(ScopedJson::Derived(Json::from(digest)))
+    }
+}
@@ -0,0 +1,69 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use handlebars::{handlebars_helper, RenderError, RenderErrorReason};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::Value as Json;
+
+use super::strings::text;
+
+/// The characters which are percent-encoded in a component of a URL, which are all but the
+/// unreserved ones of RFC 3986.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+handlebars_helper!(b64enc: |value: str| STANDARD.encode(value));
+handlebars_helper!(b64dec: |value: str| decode(&STANDARD, "b64dec", value)?);
+handlebars_helper!(b64enc_url: |value: str| URL_SAFE_NO_PAD.encode(value));
+handlebars_helper!(b64dec_url: |value: str| decode(&URL_SAFE_NO_PAD, "b64dec_url", value)?);
+handlebars_helper!(urlencode: |value: str| utf8_percent_encode(value, COMPONENT).to_string());
+handlebars_helper!(urldecode: |value: str| {
+    percent_decode_str(value).decode_utf8().map_err(|err| {
+        RenderErrorReason::Other(format!("urldecode could not decode '{}': {}", value, err))
+    })?
+});
+handlebars_helper!(url_join: |base: str, *params| {
+    // The parameters include the base itself.
+    params.iter().skip(1).fold(base.to_owned(), |url, path| {
+        let path = text(path);
+        format!("{}/{}", url.trim_end_matches('/'), path.trim_start_matches('/'))
+    })
+});
+
+// The signature of the token is not verified, so the claims are only as trustworthy as where the
+// token came from.
+handlebars_helper!(jwt_claims: |token: str| {
+    let invalid = |reason: String| {
+        RenderErrorReason::Other(format!("jwt_claims could not read the token: {}", reason))
+    };
+    let payload = token
+        .trim()
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| invalid("it has no payload after a '.'".to_owned()))?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|err| invalid(err.to_string()))?;
+    let claims = serde_json::from_slice::<Json>(&bytes).map_err(|err| invalid(err.to_string()))?;
+    if !claims.is_object() {
+        return Err(invalid("its payload is not an object".to_owned()).into());
+    }
+    claims
+});
+
+/// Decode a string of base64 with the engine, failing the render of the helper with the given name
+/// if it is not valid or does not decode to UTF-8 text.
+fn decode(engine: &impl Engine, helper: &str, value: &str) -> Result<String, RenderError> {
+    let invalid = |reason: String| {
+        RenderErrorReason::Other(format!(
+            "{} could not decode '{}': {}",
+            helper, value, reason
+        ))
+    };
+    let bytes = engine
+        .decode(value.trim())
+        .map_err(|err| invalid(err.to_string()))?;
+    Ok(String::from_utf8(bytes).map_err(|err| invalid(err.to_string()))?)
+}
@@ -0,0 +1,72 @@
+use chrono::Utc;
+use handlebars::handlebars_helper;
+
+use super::time::read_date;
+
+/// The units of file sizes after bytes, each 1024 times the one before.
+const SIZE_UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// The units of durations with how many seconds each is, largest first.
+const DURATION_UNITS: &[(&str, u64)] = &[("d", 86400), ("h", 3600), ("m", 60), ("s", 1)];
+
+/// The units of relative times with how many seconds each is, largest first.
+const RELATIVE_UNITS: &[(&str, i64)] = &[
+    ("year", 365 * 86400),
+    ("month", 30 * 86400),
+    ("week", 7 * 86400),
+    ("day", 86400),
+    ("hour", 3600),
+    ("minute", 60),
+];
+
+handlebars_helper!(filesize: |bytes: f64| {
+    let mut size = bytes;
+    let mut unit = None;
+    for next in SIZE_UNITS {
+        if size.abs() < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = Some(next);
+    }
+    match unit {
+        Some(unit) => format!("{:.1} {}", size, unit),
+        None => format!("{} B", bytes),
+    }
+});
+handlebars_helper!(duration: |seconds: u64| {
+    let mut rest = seconds;
+    let mut amounts = Vec::new();
+    for (unit, length) in DURATION_UNITS {
+        amounts.push((rest / length, unit));
+        rest %= length;
+    }
+    // Only the largest unit there is any of is shown, along with the one after it.
+    match amounts.iter().position(|(amount, _)| *amount > 0) {
+        Some(first) => amounts[first..]
+            .iter()
+            .take(2)
+            .filter(|(amount, _)| *amount > 0)
+            .map(|(amount, unit)| format!("{}{}", amount, unit))
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => "0s".to_owned(),
+    }
+});
+handlebars_helper!(ago: |value: Json| {
+    let date = read_date("ago", value)?;
+    let seconds = Utc::now().signed_duration_since(date).num_seconds();
+    let relative = RELATIVE_UNITS
+        .iter()
+        .find(|(_, length)| seconds.abs() >= *length)
+        .map(|(unit, length)| {
+            let amount = seconds.abs() / length;
+            let plural = if amount == 1 { "" } else { "s" };
+            (amount, unit, plural)
+        });
+    match relative {
+        Some((amount, unit, plural)) if seconds > 0 => format!("{} {}{} ago", amount, unit, plural),
+        Some((amount, unit, plural)) => format!("in {} {}{}", amount, unit, plural),
+        None => "just now".to_owned(),
+    }
+});
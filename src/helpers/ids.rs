@@ -0,0 +1,25 @@
+use ::uuid::Uuid;
+use handlebars::{handlebars_helper, RenderError, RenderErrorReason};
+
+handlebars_helper!(uuid: | | Uuid::new_v4().to_string());
+handlebars_helper!(uuidv5: |namespace: str, name: str| {
+    Uuid::new_v5(&parse_namespace(namespace)?, name.as_bytes()).to_string()
+});
+
+/// The namespace of a version 5 UUID, which is either one of the well-known `dns`, `url`, `oid`
+/// and `x500` namespaces or a UUID itself.
+fn parse_namespace(namespace: &str) -> Result<Uuid, RenderError> {
+    let parsed = match namespace {
+        "dns" => Uuid::NAMESPACE_DNS,
+        "url" => Uuid::NAMESPACE_URL,
+        "oid" => Uuid::NAMESPACE_OID,
+        "x500" => Uuid::NAMESPACE_X500,
+        _ => Uuid::parse_str(namespace).map_err(|err| {
+            RenderErrorReason::Other(format!(
+                "uuidv5 namespace '{}' is neither dns, url, oid, x500 nor a UUID: {}",
+                namespace, err
+            ))
+        })?,
+    };
+    Ok(parsed)
+}
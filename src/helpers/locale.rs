@@ -0,0 +1,220 @@
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperDef, RenderContext, RenderError,
+    RenderErrorReason, ScopedJson,
+};
+use intl_pluralrules::{PluralCategory, PluralRuleType, PluralRules};
+use serde_json::Value as Json;
+use unic_langid::LanguageIdentifier;
+
+use super::math::to_number;
+
+/// The locale numbers are formatted for unless another is given.
+const DEFAULT_LOCALE: &str = "en-US";
+
+/// The most decimals numbers can be rounded to.
+const MAX_DECIMALS: u64 = 20;
+
+/// The separators between groups of thousands and before the decimals of the locales numbers can
+/// be formatted for, by language with or without a region. Those with a region come before the
+/// language alone, which is used for any other region.
+const SEPARATORS: &[(&str, &str, &str)] = &[
+    ("de-ch", "’", "."),
+    ("de-li", "’", "."),
+    ("it-ch", "’", "."),
+    ("pt-br", ".", ","),
+    ("en", ",", "."),
+    ("ja", ",", "."),
+    ("ko", ",", "."),
+    ("zh", ",", "."),
+    ("he", ",", "."),
+    ("th", ",", "."),
+    ("de", ".", ","),
+    ("da", ".", ","),
+    ("el", ".", ","),
+    ("es", ".", ","),
+    ("id", ".", ","),
+    ("it", ".", ","),
+    ("nl", ".", ","),
+    ("tr", ".", ","),
+    ("fr", "\u{202f}", ","),
+    ("cs", "\u{a0}", ","),
+    ("fi", "\u{a0}", ","),
+    ("hu", "\u{a0}", ","),
+    ("nb", "\u{a0}", ","),
+    ("pl", "\u{a0}", ","),
+    ("pt", "\u{a0}", ","),
+    ("ru", "\u{a0}", ","),
+    ("sk", "\u{a0}", ","),
+    ("sv", "\u{a0}", ","),
+    ("uk", "\u{a0}", ","),
+];
+
+/// The separators between groups of thousands and before the decimals for a locale such as de-DE,
+/// de_DE or de, if it is known.
+fn separators(locale: &str) -> Option<(&'static str, &'static str)> {
+    let locale = locale.replace('_', "-").to_lowercase();
+    let language = locale.split('-').next().unwrap_or_default();
+    SEPARATORS
+        .iter()
+        .find(|(name, _, _)| *name == locale)
+        .or_else(|| SEPARATORS.iter().find(|(name, _, _)| *name == language))
+        .map(|(_, group, decimal)| (*group, *decimal))
+}
+
+handlebars_helper!(plural: |count: Json, singular: str, plural_form: str| {
+    let count = to_number(count).ok_or(RenderErrorReason::InvalidParamType("number"))?;
+    if count.as_f64() == Some(1.0) {
+        singular.to_owned()
+    } else {
+        plural_form.to_owned()
+    }
+});
+
+/// The plural rules of a locale such as pl-PL, pl_PL or pl, falling back to those of its language,
+/// if they are known.
+fn plural_rules(locale: &str) -> Option<PluralRules> {
+    let locale = locale.replace('_', "-");
+    let language = locale.split('-').next().unwrap_or_default();
+    [locale.as_str(), language]
+        .iter()
+        .filter_map(|name| name.parse::<LanguageIdentifier>().ok())
+        .find_map(|langid| PluralRules::create(langid, PluralRuleType::CARDINAL).ok())
+}
+
+/// The helper choosing the form of a word or phrase for a count by the plural rules of a locale,
+/// given as the forms for each of the categories of those rules, with `#` in them replaced by the
+/// count.
+pub struct PluralSelect;
+
+impl HelperDef for PluralSelect {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let count = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("plural_select", 0))?;
+        let count =
+            to_number(count.value()).ok_or(RenderErrorReason::InvalidParamType("number"))?;
+        let locale = match h.hash_get("locale") {
+            Some(locale) => locale
+                .value()
+                .as_str()
+                .ok_or(RenderErrorReason::InvalidParamType("string"))?,
+            None => DEFAULT_LOCALE,
+        };
+        let rules = plural_rules(locale).ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "plural_select does not know the plural rules of the locale '{}'",
+                locale
+            ))
+        })?;
+
+        let count = count.to_string();
+        let category = match rules.select(count.as_str()) {
+            Ok(PluralCategory::ZERO) => "zero",
+            Ok(PluralCategory::ONE) => "one",
+            Ok(PluralCategory::TWO) => "two",
+            Ok(PluralCategory::FEW) => "few",
+            Ok(PluralCategory::MANY) => "many",
+            Ok(PluralCategory::OTHER) | Err(_) => "other",
+        };
+        let form = h
+            .hash_get(category)
+            .or_else(|| h.hash_get("other"))
+            .and_then(|form| form.value().as_str())
+            .ok_or_else(|| {
+                RenderErrorReason::Other(format!(
+                    "plural_select has no form for {}, which is {} in '{}', and no other form",
+                    count, category, locale
+                ))
+            })?;
+        Ok(ScopedJson::Derived(Json::from(form.replace('#', &count))))
+    }
+}
+
+/// The helper formatting a number with the separators of a locale, optionally rounded to a number
+/// of decimals.
+pub struct NumFormat;
+
+impl HelperDef for NumFormat {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("num_format", 0))?;
+        let number =
+            to_number(value.value()).ok_or(RenderErrorReason::InvalidParamType("number"))?;
+        let locale = match h.hash_get("locale") {
+            Some(locale) => locale
+                .value()
+                .as_str()
+                .ok_or(RenderErrorReason::InvalidParamType("string"))?,
+            None => DEFAULT_LOCALE,
+        };
+        let (group, decimal) = separators(locale).ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "num_format does not know the separators of the locale '{}'",
+                locale
+            ))
+        })?;
+
+        let plain = match h.hash_get("decimals") {
+            Some(decimals) => {
+                let decimals = decimals
+                    .value()
+                    .as_u64()
+                    .ok_or(RenderErrorReason::InvalidParamType("non-negative integer"))?;
+                if decimals > MAX_DECIMALS {
+                    return Err(RenderErrorReason::Other(format!(
+                        "num_format can round to at most {} decimals, not {}",
+                        MAX_DECIMALS, decimals
+                    ))
+                    .into());
+                }
+                match number.as_f64() {
+                    Some(float) if number.is_f64() => format!("{:.*}", decimals as usize, float),
+                    // Integers are kept exact, rather than rounded to the nearest float.
+                    _ if decimals > 0 => format!("{}.{}", number, "0".repeat(decimals as usize)),
+                    _ => number.to_string(),
+                }
+            }
+            // Unlike the number itself, floats are shown without an exponent, such as 1e21.
+            None => match number.as_f64() {
+                Some(float) if number.is_f64() => float.to_string(),
+                _ => number.to_string(),
+            },
+        };
+        // Numbers rounded to zero, such as -0.001 to two decimals, lose their sign.
+        let (sign, plain) = match plain.strip_prefix('-') {
+            Some(plain) if plain.bytes().any(|digit| matches!(digit, b'1'..=b'9')) => ("-", plain),
+            Some(plain) => ("", plain),
+            None => ("", plain.as_str()),
+        };
+        let (integer, fraction) = match plain.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (plain, None),
+        };
+
+        let mut formatted = sign.to_owned();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                formatted.push_str(group);
+            }
+            formatted.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            formatted.push_str(decimal);
+            formatted.push_str(fraction);
+        }
+        Ok(ScopedJson::Derived(Json::from(formatted)))
+    }
+}
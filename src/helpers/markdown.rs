@@ -0,0 +1,120 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, Renderable, ScopedJson,
+};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use serde_json::Value as Json;
+
+use super::strings::text;
+
+/// The schemes links and images may point to in Markdown which is not trusted, besides paths and
+/// URLs without a scheme.
+const SAFE_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// The helper rendering Markdown as HTML, of a string or of its block. What it writes is not
+/// escaped, as it is HTML already, and neither is the block before it is rendered as Markdown,
+/// which would break its quotes and links such as <https://example.com>.
+pub struct Markdown;
+
+impl HelperDef for Markdown {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let source = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("markdown", 0))?;
+        let rendered = to_html(&text(source.value()), keeps_html(h)?);
+        Ok(ScopedJson::Derived(Json::from(rendered)))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let rendered = match h.template() {
+            Some(template) => {
+                let disable_escape = rc.is_disable_escape();
+                rc.set_disable_escape(true);
+                let source = template.renders(r, ctx, rc);
+                rc.set_disable_escape(disable_escape);
+                to_html(&source?, keeps_html(h)?)
+            }
+            None => self.call_inner(h, r, ctx, rc)?.render(),
+        };
+        out.write(&rendered)?;
+        Ok(())
+    }
+}
+
+/// Whether the HTML inside the Markdown is to be kept as it is, as `html` says, rather than
+/// escaped as text for Markdown which is not trusted, with its links and images only pointing to
+/// safe schemes.
+fn keeps_html(h: &Helper) -> Result<bool, RenderError> {
+    match h.hash_get("html") {
+        Some(html) => Ok(html
+            .value()
+            .as_bool()
+            .ok_or(RenderErrorReason::InvalidParamType("boolean"))?),
+        None => Ok(true),
+    }
+}
+
+/// The HTML for Markdown in the CommonMark syntax, with tables, strikethrough, task lists and
+/// footnotes as GitHub has them.
+///
+/// Unless the HTML is kept, it is escaped as text, and links and images pointing to schemes other
+/// than those in `SAFE_SCHEMES`, such as javascript:, point nowhere instead.
+fn to_html(source: &str, keep_html: bool) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    let events = Parser::new_ext(source, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) if !keep_html => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !keep_html && !is_safe(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: CowStr::Borrowed(""),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !keep_html && !is_safe(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: CowStr::Borrowed(""),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut rendered = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut rendered, events);
+    rendered
+}
+
+/// Whether a link or image may point to the URL in Markdown which is not trusted, because it is a
+/// path or has one of the `SAFE_SCHEMES`.
+fn is_safe(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => SAFE_SCHEMES
+            .iter()
+            .any(|safe| scheme.trim().eq_ignore_ascii_case(safe)),
+        _ => true,
+    }
+}
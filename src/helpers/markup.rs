@@ -0,0 +1,72 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, ScopedJson,
+};
+use serde_json::Value as Json;
+
+use super::strings::text;
+use crate::render::xml_escape;
+
+/// An enumeration of the places in XML and HTML helpers can escape values for.
+#[derive(Clone, Copy)]
+pub enum Markup {
+    /// The text of an element.
+    Text,
+    /// The value of an attribute, in which tabs and line breaks would otherwise be normalized to
+    /// spaces.
+    Attribute,
+}
+
+impl Markup {
+    /// The name of the helper escaping values for this place.
+    fn helper(self) -> &'static str {
+        match self {
+            Markup::Text => "xml_escape",
+            Markup::Attribute => "attr_escape",
+        }
+    }
+
+    /// The value escaped for this place.
+    fn escape(self, value: &str) -> String {
+        let escaped = xml_escape(value);
+        match self {
+            Markup::Text => escaped,
+            Markup::Attribute => escaped
+                .replace('\t', "&#9;")
+                .replace('\n', "&#10;")
+                .replace('\r', "&#13;"),
+        }
+    }
+}
+
+/// The helper escaping a value for XML and HTML whatever the escaping of the registry is. What it
+/// writes is not escaped again.
+pub struct MarkupEscape(pub Markup);
+
+impl HelperDef for MarkupEscape {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(self.0.helper(), 0))?;
+        let escaped = self.0.escape(&text(value.value()));
+        Ok(ScopedJson::Derived(Json::from(escaped)))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        out.write(&self.call_inner(h, r, ctx, rc)?.render())?;
+        Ok(())
+    }
+}
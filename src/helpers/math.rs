@@ -0,0 +1,218 @@
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperDef, RenderContext, RenderError,
+    RenderErrorReason, ScopedJson,
+};
+use serde_json::Value as Json;
+use std::cmp::Ordering;
+
+/// An enumeration of the arithmetic operations helpers can do on two numbers.
+#[derive(Clone, Copy)]
+pub enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+impl Operation {
+    /// The name of the helper doing this operation.
+    fn helper(self) -> &'static str {
+        match self {
+            Operation::Add => "add",
+            Operation::Sub => "sub",
+            Operation::Mul => "mul",
+            Operation::Div => "div",
+            Operation::Mod => "mod",
+        }
+    }
+
+    /// The result of this operation on two integers, or nothing if it is not an integer itself.
+    fn integers(self, a: i64, b: i64) -> Option<i64> {
+        match self {
+            Operation::Add => a.checked_add(b),
+            Operation::Sub => a.checked_sub(b),
+            Operation::Mul => a.checked_mul(b),
+            Operation::Div => a
+                .checked_rem(b)
+                .filter(|rem| *rem == 0)
+                .and(a.checked_div(b)),
+            Operation::Mod => a.checked_rem(b),
+        }
+    }
+
+    /// The result of this operation on two numbers which are not both integers.
+    fn floats(self, a: f64, b: f64) -> f64 {
+        match self {
+            Operation::Add => a + b,
+            Operation::Sub => a - b,
+            Operation::Mul => a * b,
+            Operation::Div => a / b,
+            Operation::Mod => a % b,
+        }
+    }
+}
+
+/// The helper doing an arithmetic operation on two numbers, giving an integer if they are both
+/// integers and the result is one too.
+pub struct Arithmetic(pub Operation);
+
+impl HelperDef for Arithmetic {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let helper = self.0.helper();
+        let number = |index| -> Result<serde_json::Number, RenderError> {
+            let value = h
+                .param(index)
+                .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, index))?
+                .value();
+            Ok(to_number(value).ok_or(RenderErrorReason::InvalidParamType("number"))?)
+        };
+        let (a, b) = (number(0)?, number(1)?);
+        let by_zero = matches!(self.0, Operation::Div | Operation::Mod) && b.as_f64() == Some(0.0);
+        if by_zero {
+            return Err(
+                RenderErrorReason::Other(format!("{} cannot divide by zero", helper)).into(),
+            );
+        }
+
+        let integer = match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => self.0.integers(a, b),
+            _ => None,
+        };
+        let result = match integer {
+            Some(integer) => Json::from(integer),
+            None => {
+                let (a, b) = (
+                    a.as_f64().unwrap_or(f64::NAN),
+                    b.as_f64().unwrap_or(f64::NAN),
+                );
+                finite(helper, self.0.floats(a, b))?
+            }
+        };
+        Ok(ScopedJson::Derived(result))
+    }
+}
+
+/// An enumeration of the ways helpers can compare two values.
+#[derive(Clone, Copy)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl Comparison {
+    /// The name of the helper making this comparison.
+    fn helper(self) -> &'static str {
+        match self {
+            Comparison::Eq => "eq",
+            Comparison::Ne => "ne",
+            Comparison::Gt => "gt",
+            Comparison::Gte => "gte",
+            Comparison::Lt => "lt",
+            Comparison::Lte => "lte",
+        }
+    }
+
+    /// Whether this comparison holds for the order of two values.
+    fn holds(self, order: Ordering) -> bool {
+        match self {
+            Comparison::Eq => order == Ordering::Equal,
+            Comparison::Ne => order != Ordering::Equal,
+            Comparison::Gt => order == Ordering::Greater,
+            Comparison::Gte => order != Ordering::Less,
+            Comparison::Lt => order == Ordering::Less,
+            Comparison::Lte => order != Ordering::Greater,
+        }
+    }
+}
+
+/// The helper comparing two values, numbers by their value whether they are integers or not and
+/// strings in lexicographic order. Any other values can only be compared for equality.
+pub struct Compare(pub Comparison);
+
+impl HelperDef for Compare {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let helper = self.0.helper();
+        let value = |index| {
+            h.param(index)
+                .map(|param| param.value())
+                .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, index))
+        };
+        let (a, b) = (value(0)?, value(1)?);
+        let order = match (a, b) {
+            (Json::Number(a), Json::Number(b)) => match (a.as_i64(), b.as_i64()) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => a.as_f64().partial_cmp(&b.as_f64()),
+            },
+            (Json::String(a), Json::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        let holds = match (order, self.0) {
+            (Some(order), comparison) => comparison.holds(order),
+            (None, Comparison::Eq) => a == b,
+            (None, Comparison::Ne) => a != b,
+            (None, _) => {
+                return Err(RenderErrorReason::Other(format!(
+                    "{} can only compare two numbers or two strings",
+                    helper
+                ))
+                .into())
+            }
+        };
+        Ok(ScopedJson::Derived(Json::from(holds)))
+    }
+}
+
+handlebars_helper!(round: |value: Json, {places: u64 = 0}| {
+    let number = to_number(value).ok_or(RenderErrorReason::InvalidParamType("number"))?;
+    match number.as_i64() {
+        Some(integer) => Json::from(integer),
+        None => {
+            let factor = 10f64.powi(places.min(i32::MAX as u64) as i32);
+            let rounded = (number.as_f64().unwrap_or(f64::NAN) * factor).round() / factor;
+            if places == 0 && rounded.abs() < i64::MAX as f64 {
+                Json::from(rounded as i64)
+            } else {
+                finite("round", rounded)?
+            }
+        }
+    }
+});
+
+/// The result of a helper as a number, failing its render if it is infinite or not a number,
+/// which JSON cannot represent.
+fn finite(helper: &str, result: f64) -> Result<Json, RenderError> {
+    if !result.is_finite() {
+        return Err(RenderErrorReason::Other(format!(
+            "{} gave {}, which is not a finite number",
+            helper, result
+        ))
+        .into());
+    }
+    Ok(Json::from(result))
+}
+
+/// The number a value is, or is written as in a string such as one from the environment.
+pub(super) fn to_number(value: &Json) -> Option<serde_json::Number> {
+    match value {
+        Json::Number(number) => Some(number.clone()),
+        Json::String(raw) => raw.trim().parse().ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,98 @@
+use std::convert::TryFrom;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use handlebars::{handlebars_helper, RenderError, RenderErrorReason};
+use ipnet::IpNet;
+
+handlebars_helper!(cidr_contains: |cidr: str, ip: str| {
+    parse_net("cidr_contains", cidr)?.contains(&parse_ip("cidr_contains", ip)?)
+});
+handlebars_helper!(cidr_host: |cidr: str, index: i64| {
+    let net = parse_net("cidr_host", cidr)?;
+    let size = 1u128
+        .checked_shl(u32::from(net.max_prefix_len() - net.prefix_len()))
+        .unwrap_or(u128::MAX);
+    let offset = if index < 0 {
+        size.checked_sub(u128::from(index.unsigned_abs()))
+    } else {
+        Some(index as u128).filter(|offset| *offset < size)
+    };
+    let host = offset.and_then(|offset| offset_ip(net.network(), offset));
+    host.ok_or_else(|| {
+        RenderErrorReason::Other(format!(
+            "cidr_host has no address {} in '{}', which has {} of them",
+            index, cidr, size
+        ))
+    })?
+    .to_string()
+});
+handlebars_helper!(ip_next: |ip: str, *args| {
+    let step = match args.get(1) {
+        Some(step) => step
+            .as_i64()
+            .ok_or(RenderErrorReason::InvalidParamType("integer"))?,
+        None => 1,
+    };
+    let ip_addr = parse_ip("ip_next", ip)?;
+    let next = if step < 0 {
+        offset_ip_back(ip_addr, u128::from(step.unsigned_abs()))
+    } else {
+        offset_ip(ip_addr, step as u128)
+    };
+    next.ok_or_else(|| {
+        RenderErrorReason::Other(format!("ip_next has no address {} after '{}'", step, ip))
+    })?
+    .to_string()
+});
+
+/// Parse a network in CIDR notation such as 10.0.0.0/8, failing the render of the helper with the
+/// given name if it is not valid.
+fn parse_net(helper: &str, cidr: &str) -> Result<IpNet, RenderError> {
+    Ok(cidr.trim().parse::<IpNet>().map_err(|err| {
+        RenderErrorReason::Other(format!(
+            "{} could not read the network '{}': {}",
+            helper, cidr, err
+        ))
+    })?)
+}
+
+/// Parse an IPv4 or IPv6 address, failing the render of the helper with the given name if it is
+/// not valid.
+fn parse_ip(helper: &str, ip: &str) -> Result<IpAddr, RenderError> {
+    Ok(ip.trim().parse::<IpAddr>().map_err(|err| {
+        RenderErrorReason::Other(format!(
+            "{} could not read the address '{}': {}",
+            helper, ip, err
+        ))
+    })?)
+}
+
+/// The address the given number of addresses before another, if there is one.
+fn offset_ip_back(ip: IpAddr, offset: u128) -> Option<IpAddr> {
+    match ip {
+        IpAddr::V4(ip) => {
+            let offset = u32::try_from(offset).ok()?;
+            let previous = u32::from(ip).checked_sub(offset)?;
+            Some(IpAddr::V4(Ipv4Addr::from(previous)))
+        }
+        IpAddr::V6(ip) => {
+            let previous = u128::from(ip).checked_sub(offset)?;
+            Some(IpAddr::V6(Ipv6Addr::from(previous)))
+        }
+    }
+}
+
+/// The address the given number of addresses after another, if there is one.
+fn offset_ip(ip: IpAddr, offset: u128) -> Option<IpAddr> {
+    match ip {
+        IpAddr::V4(ip) => {
+            let offset = u32::try_from(offset).ok()?;
+            let next = u32::from(ip).checked_add(offset)?;
+            Some(IpAddr::V4(Ipv4Addr::from(next)))
+        }
+        IpAddr::V6(ip) => {
+            let next = u128::from(ip).checked_add(offset)?;
+            Some(IpAddr::V6(Ipv6Addr::from(next)))
+        }
+    }
+}
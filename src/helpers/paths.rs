@@ -0,0 +1,79 @@
+use std::path::{Component, Path, PathBuf};
+
+use handlebars::{handlebars_helper, RenderErrorReason};
+
+use super::strings::text;
+
+handlebars_helper!(dirname: |path: str| {
+    match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_string_lossy().into_owned(),
+        _ if Path::new(path).has_root() => path.to_owned(),
+        _ => ".".to_owned(),
+    }
+});
+handlebars_helper!(basename: |path: str| {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+});
+handlebars_helper!(ext: |path: str| {
+    Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().into_owned())
+        .unwrap_or_default()
+});
+handlebars_helper!(path_join: |*params| {
+    params
+        .iter()
+        .fold(PathBuf::new(), |joined, part| joined.join(text(part).as_ref()))
+        .to_string_lossy()
+        .into_owned()
+});
+handlebars_helper!(relpath: |from: str, to: str| {
+    relative(Path::new(from), Path::new(to))
+        .ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "relpath cannot tell the path to '{}' from '{}' without reading the file system",
+                to, from
+            ))
+        })?
+        .to_string_lossy()
+        .into_owned()
+});
+
+/// The path to a path from a directory, worked out from the paths alone. There is none if one is
+/// absolute and the other not, or if the directory goes up out of where the other path is.
+fn relative(from: &Path, to: &Path) -> Option<PathBuf> {
+    if from.has_root() != to.has_root() {
+        return None;
+    }
+    let (from, to) = (parts(from), parts(to));
+    let common = from
+        .iter()
+        .zip(&to)
+        .take_while(|(from, to)| from == to)
+        .count();
+    if from[common..].contains(&Component::ParentDir) {
+        return None;
+    }
+
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push(Component::ParentDir);
+    }
+    for component in &to[common..] {
+        path.push(component);
+    }
+    if path.as_os_str().is_empty() {
+        path.push(Component::CurDir);
+    }
+    Some(path)
+}
+
+/// The components of a path which are not the current directory.
+fn parts(path: &Path) -> Vec<Component<'_>> {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
@@ -0,0 +1,58 @@
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext,
+    RenderError, RenderErrorReason, Renderable,
+};
+use regex::Regex;
+use serde_json::Value as Json;
+
+handlebars_helper!(regex_match: |value: str, pattern: str| compile(pattern)?.is_match(value));
+handlebars_helper!(regex_replace: |value: str, pattern: str, replacement: str| {
+    compile(pattern)?.replace_all(value, replacement).into_owned()
+});
+handlebars_helper!(regex_capture: |value: str, pattern: str| {
+    match compile(pattern)?.captures(value) {
+        Some(captures) => captures
+            .iter()
+            .map(|group| group.map_or(Json::Null, |group| Json::from(group.as_str())))
+            .collect(),
+        None => Json::Null,
+    }
+});
+
+/// The block helper rendering its block if a string matches a regular expression, or its else
+/// block otherwise.
+pub struct IfRegex;
+
+impl HelperDef for IfRegex {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let param = |index| {
+            h.param(index)
+                .ok_or(RenderErrorReason::ParamNotFoundForIndex("if_regex", index))?
+                .value()
+                .as_str()
+                .ok_or(RenderErrorReason::InvalidParamType("string"))
+        };
+        let matched = compile(param(1)?)?.is_match(param(0)?);
+
+        let template = if matched { h.template() } else { h.inverse() };
+        match template {
+            Some(template) => template.render(r, ctx, rc, out),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Compile a regular expression given to a helper, failing the render if it is not valid.
+fn compile(pattern: &str) -> Result<Regex, RenderError> {
+    Regex::new(pattern).map_err(|err| {
+        RenderErrorReason::Other(format!("invalid regular expression '{}': {}", pattern, err))
+            .into()
+    })
+}
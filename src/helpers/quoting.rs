@@ -0,0 +1,226 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, ScopedJson,
+};
+use serde_json::Value as Json;
+
+use super::strings::text;
+use crate::render::shell_escape;
+
+/// The characters which need no quoting in a POSIX shell word.
+const SHELL_SAFE: &str = "@%+=:,./-_";
+
+/// The characters PowerShell ends a single-quoted string at, which are doubled inside one.
+const POWERSHELL_QUOTES: &[char] = &['\'', '\u{2018}', '\u{2019}', '\u{201a}', '\u{201b}'];
+
+/// An enumeration of the languages helpers can quote values for.
+#[derive(Clone, Copy)]
+pub enum Quoting {
+    Posix,
+    PowerShell,
+}
+
+impl Quoting {
+    /// The name of the helper quoting values for this language.
+    fn helper(self) -> &'static str {
+        match self {
+            Quoting::Posix => "shell_quote",
+            Quoting::PowerShell => "ps_quote",
+        }
+    }
+
+    /// The value quoted so that it is read back as a single word with the same text.
+    fn quote(self, value: &str) -> String {
+        match self {
+            Quoting::Posix => {
+                let safe = !value.is_empty()
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || SHELL_SAFE.contains(c));
+                if safe {
+                    value.to_owned()
+                } else {
+                    shell_escape(value)
+                }
+            }
+            Quoting::PowerShell => {
+                let mut quoted = String::from("'");
+                for c in value.chars() {
+                    if POWERSHELL_QUOTES.contains(&c) {
+                        quoted.push(c);
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('\'');
+                quoted
+            }
+        }
+    }
+}
+
+/// The helper quoting a value for safely embedding it in a script. What it writes is not escaped,
+/// as that would change what the script reads back.
+pub struct Quote(pub Quoting);
+
+impl HelperDef for Quote {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(self.0.helper(), 0))?;
+        let quoted = self.0.quote(&text(value.value()));
+        Ok(ScopedJson::Derived(Json::from(quoted)))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        out.write(&self.call_inner(h, r, ctx, rc)?.render())?;
+        Ok(())
+    }
+}
+
+/// The SQL dialect values are quoted for unless another is given.
+const DEFAULT_DIALECT: &str = "ansi";
+
+/// An enumeration of the SQL dialects helpers can quote values for.
+#[derive(Clone, Copy)]
+enum Dialect {
+    Ansi,
+    Postgres,
+    Mysql,
+    Sqlite,
+    Mssql,
+}
+
+impl Dialect {
+    /// The dialect with the given name, if it is known.
+    fn parse(name: &str) -> Option<Dialect> {
+        match name.to_lowercase().as_str() {
+            "ansi" => Some(Dialect::Ansi),
+            "postgres" | "postgresql" => Some(Dialect::Postgres),
+            "mysql" | "mariadb" => Some(Dialect::Mysql),
+            "sqlite" => Some(Dialect::Sqlite),
+            "mssql" | "sqlserver" => Some(Dialect::Mssql),
+            _ => None,
+        }
+    }
+}
+
+/// An enumeration of the parts of SQL statements helpers can quote values as.
+#[derive(Clone, Copy)]
+pub enum Sql {
+    /// A string literal, or NULL for a null value.
+    Literal,
+    /// The name of a table, column or other object.
+    Identifier,
+}
+
+impl Sql {
+    /// The name of the helper quoting values as this part of a statement.
+    fn helper(self) -> &'static str {
+        match self {
+            Sql::Literal => "sql_str",
+            Sql::Identifier => "sql_ident",
+        }
+    }
+
+    /// The value quoted as this part of a statement in the dialect.
+    fn quote(self, value: &str, dialect: Dialect) -> String {
+        match (self, dialect) {
+            (Sql::Literal, Dialect::Mysql) => {
+                let mut quoted = String::from("'");
+                for c in value.chars() {
+                    match c {
+                        '\'' => quoted.push_str("''"),
+                        '\\' => quoted.push_str("\\\\"),
+                        c => quoted.push(c),
+                    }
+                }
+                quoted.push('\'');
+                quoted
+            }
+            (Sql::Literal, _) => format!("'{}'", value.replace('\'', "''")),
+            (Sql::Identifier, Dialect::Mysql) => format!("`{}`", value.replace('`', "``")),
+            (Sql::Identifier, Dialect::Mssql) => format!("[{}]", value.replace(']', "]]")),
+            (Sql::Identifier, _) => format!("\"{}\"", value.replace('"', "\"\"")),
+        }
+    }
+}
+
+/// The helper quoting a value as a string literal or an identifier of an SQL statement in the
+/// dialect given as `dialect`. What it writes is not escaped, as that would change the statement.
+pub struct SqlQuote(pub Sql);
+
+impl HelperDef for SqlQuote {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let helper = self.0.helper();
+        let value = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, 0))?
+            .value();
+        let name = match h.hash_get("dialect") {
+            Some(name) => name
+                .value()
+                .as_str()
+                .ok_or(RenderErrorReason::InvalidParamType("string"))?,
+            None => DEFAULT_DIALECT,
+        };
+        let dialect = Dialect::parse(name).ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "{} does not know the SQL dialect '{}', which must be ansi, postgres, mysql, \
+                 sqlite or mssql",
+                helper, name
+            ))
+        })?;
+
+        if let (Sql::Literal, Json::Null) = (self.0, value) {
+            return Ok(ScopedJson::Derived(Json::from("NULL")));
+        }
+        let value = text(value);
+        if value.contains('\0') {
+            return Err(RenderErrorReason::Other(format!(
+                "{} cannot quote a value containing a NUL character",
+                helper
+            ))
+            .into());
+        }
+        if let (Sql::Identifier, true) = (self.0, value.is_empty()) {
+            return Err(RenderErrorReason::Other(
+                "sql_ident cannot quote an empty name".to_owned(),
+            )
+            .into());
+        }
+        Ok(ScopedJson::Derived(Json::from(
+            self.0.quote(&value, dialect),
+        )))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        out.write(&self.call_inner(h, r, ctx, rc)?.render())?;
+        Ok(())
+    }
+}
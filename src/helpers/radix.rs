@@ -0,0 +1,106 @@
+use std::convert::TryFrom;
+
+use handlebars::{handlebars_helper, RenderError, RenderErrorReason};
+use serde_json::Value as Json;
+
+/// The digits of numbers in any base up to 36.
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+handlebars_helper!(hex: |value: i64, {width: u64 = 0, prefix: bool = false}| {
+    let prefix = if prefix { "0x" } else { "" };
+    write_radix(value, 16, width, prefix)
+});
+handlebars_helper!(from_hex: |value: str| read_radix("from_hex", value, 16)?);
+handlebars_helper!(to_radix: |value: i64, base: u64, {width: u64 = 0}| {
+    write_radix(value, check_base("to_radix", base)?, width, "")
+});
+handlebars_helper!(from_radix: |value: str, base: u64| {
+    read_radix("from_radix", value, check_base("from_radix", base)?)?
+});
+handlebars_helper!(hex_bytes: |value: Json, {sep: str = " "}| {
+    let bytes = match &value {
+        Json::String(value) => value.as_bytes().to_vec(),
+        Json::Array(values) => values
+            .iter()
+            .map(|value| value.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                RenderErrorReason::Other(
+                    "hex_bytes can only format arrays of numbers from 0 to 255".to_owned(),
+                )
+            })?,
+        _ => return Err(RenderErrorReason::InvalidParamType("string or array").into()),
+    };
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(sep)
+});
+
+/// The base of numbers given to the helper with the given name, failing its render if it is not
+/// from 2 to 36.
+fn check_base(helper: &str, base: u64) -> Result<u32, RenderError> {
+    match u32::try_from(base) {
+        Ok(base) if (2..=36).contains(&base) => Ok(base),
+        _ => Err(RenderErrorReason::Other(format!(
+            "{} cannot use base {}, which must be from 2 to 36",
+            helper, base
+        ))
+        .into()),
+    }
+}
+
+/// The digits of a number in a base, with zeros before them to make them at least the given width,
+/// after the prefix and any minus sign.
+fn write_radix(value: i64, base: u32, width: u64, prefix: &str) -> String {
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        digits.push(DIGITS[(magnitude % u64::from(base)) as usize]);
+        magnitude /= u64::from(base);
+        if magnitude == 0 {
+            break;
+        }
+    }
+    while (digits.len() as u64) < width {
+        digits.push(b'0');
+    }
+    digits.reverse();
+    let sign = if value < 0 { "-" } else { "" };
+    format!("{}{}{}", sign, prefix, String::from_utf8_lossy(&digits))
+}
+
+/// Read a number written in a base, with an optional minus sign and, for hexadecimal, an optional
+/// 0x prefix, failing the render of the helper with the given name if it is not valid.
+fn read_radix(helper: &str, value: &str, base: u32) -> Result<i64, RenderError> {
+    let trimmed = value.trim();
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, trimmed),
+    };
+    let digits = match base {
+        16 => digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+            .unwrap_or(digits),
+        _ => digits,
+    };
+    let digits = digits.replace('_', "");
+    let parsed = u64::from_str_radix(&digits, base)
+        .ok()
+        .and_then(|magnitude| {
+            if negative {
+                0i64.checked_sub_unsigned(magnitude)
+            } else {
+                i64::try_from(magnitude).ok()
+            }
+        })
+        .ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "{} could not read '{}' as a number in base {}",
+                helper, value, base
+            ))
+        })?;
+    Ok(parsed)
+}
@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use rand::distr::Alphanumeric;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{RngExt, SeedableRng};
+use serde_json::Value as Json;
+
+/// The generator of random values shared by the random helpers of a registry, so that the values
+/// they give are the same on every run given the same seed.
+#[derive(Clone)]
+pub struct Source(Arc<Mutex<StdRng>>);
+
+impl Source {
+    /// A generator seeded with the given seed, or from the operating system if none is given.
+    pub fn new(seed: Option<u64>) -> Source {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => rand::make_rng(),
+        };
+        Source(Arc::new(Mutex::new(rng)))
+    }
+
+    /// Run a function with the generator.
+    fn with<T>(&self, f: impl FnOnce(&mut StdRng) -> T) -> T {
+        let mut rng = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut rng)
+    }
+}
+
+/// The parameter of a helper at an index.
+fn param<'a>(h: &'a Helper, helper: &'static str, index: usize) -> Result<&'a Json, RenderError> {
+    Ok(h.param(index)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, index))?
+        .value())
+}
+
+/// The helper giving a random integer between two others, including both.
+pub struct RandomInt(pub Source);
+
+impl HelperDef for RandomInt {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let integer = |index| -> Result<i64, RenderError> {
+            Ok(param(h, "random_int", index)?
+                .as_i64()
+                .ok_or(RenderErrorReason::InvalidParamType("integer"))?)
+        };
+        let (min, max) = (integer(0)?, integer(1)?);
+        if min > max {
+            return Err(RenderErrorReason::Other(format!(
+                "random_int has no integers between {} and {}",
+                min, max
+            ))
+            .into());
+        }
+        let value = self.0.with(|rng| rng.random_range(min..=max));
+        Ok(ScopedJson::Derived(Json::from(value)))
+    }
+}
+
+/// The helper giving a random element of an array.
+pub struct RandomChoice(pub Source);
+
+impl HelperDef for RandomChoice {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let values = param(h, "random_choice", 0)?
+            .as_array()
+            .ok_or(RenderErrorReason::InvalidParamType("array"))?;
+        let value = self
+            .0
+            .with(|rng| values.choose(rng).cloned())
+            .ok_or_else(|| {
+                RenderErrorReason::Other("random_choice has no elements to choose from".to_owned())
+            })?;
+        Ok(ScopedJson::Derived(value))
+    }
+}
+
+/// The helper giving a random string of letters and digits of a given length.
+pub struct RandomString(pub Source);
+
+impl HelperDef for RandomString {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let length = param(h, "random_string", 0)?
+            .as_u64()
+            .ok_or(RenderErrorReason::InvalidParamType("non-negative integer"))?;
+        let value = self.0.with(|rng| {
+            (0..length)
+                .map(|_| char::from(rng.sample(Alphanumeric)))
+                .collect::<String>()
+        });
+        Ok(ScopedJson::Derived(Json::from(value)))
+    }
+}
@@ -0,0 +1,137 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, ScopedJson,
+};
+use serde_json::Value as Json;
+
+/// An enumeration of the formats values can be written in by helpers.
+#[derive(Clone, Copy)]
+pub enum Syntax {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Syntax {
+    /// The name of the helper writing values in this format.
+    fn helper(self) -> &'static str {
+        match self {
+            Syntax::Json => "toJson",
+            Syntax::Yaml => "toYaml",
+            Syntax::Toml => "toToml",
+        }
+    }
+
+    /// The name of the helper parsing values in this format.
+    fn parser(self) -> &'static str {
+        match self {
+            Syntax::Json => "fromJson",
+            Syntax::Yaml => "fromYaml",
+            Syntax::Toml => "fromToml",
+        }
+    }
+
+    /// The value of a string in this format.
+    fn parse(self, raw: &str) -> Result<Json, RenderError> {
+        let parsed = match self {
+            Syntax::Json => serde_json::from_str(raw).map_err(|err| err.to_string()),
+            Syntax::Yaml => serde_yaml::from_str(raw).map_err(|err| err.to_string()),
+            Syntax::Toml => toml::from_str(raw).map_err(|err| err.to_string()),
+        };
+        parsed.map_err(|err| {
+            RenderErrorReason::Other(format!(
+                "{} could not parse the string: {}",
+                self.parser(),
+                err
+            ))
+            .into()
+        })
+    }
+
+    /// The value written in this format, without a newline at the end, as JSON on a single line
+    /// unless it is to be pretty.
+    fn write(self, value: &Json, pretty: bool) -> Result<String, RenderError> {
+        let written = match self {
+            Syntax::Json if pretty => {
+                serde_json::to_string_pretty(value).map_err(|err| err.to_string())
+            }
+            Syntax::Json => serde_json::to_string(value).map_err(|err| err.to_string()),
+            Syntax::Yaml => serde_yaml::to_string(value).map_err(|err| err.to_string()),
+            Syntax::Toml => toml::to_string(value).map_err(|err| err.to_string()),
+        };
+        let written = written.map_err(|err| {
+            RenderErrorReason::Other(format!(
+                "{} could not write the value: {}",
+                self.helper(),
+                err
+            ))
+        })?;
+        Ok(written.trim_end_matches('\n').to_owned())
+    }
+}
+
+/// The helper writing a value in a format, with every line after the first indented by the number
+/// of spaces given as `indent`. What it writes is not escaped, as that would no longer be valid in
+/// the format.
+pub struct To(pub Syntax);
+
+impl HelperDef for To {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(self.0.helper(), 0))?;
+        let pretty = h
+            .hash_get("pretty")
+            .is_some_and(|pretty| pretty.value().as_bool() == Some(true));
+        let written = self.0.write(value.value(), pretty)?;
+        let indent = h
+            .hash_get("indent")
+            .and_then(|indent| indent.value().as_u64());
+        let written = match indent {
+            Some(indent) => written.replace('\n', &format!("\n{}", " ".repeat(indent as usize))),
+            None => written,
+        };
+        Ok(ScopedJson::Derived(Json::from(written)))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        out.write(&self.call_inner(h, r, ctx, rc)?.render())?;
+        Ok(())
+    }
+}
+
+/// The helper parsing a string in a format into the value it holds, to be used by other helpers
+/// such as `{{#each (fromJson payload)}}`.
+pub struct Parse(pub Syntax);
+
+impl HelperDef for Parse {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let raw = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(self.0.parser(), 0))?;
+        let raw = raw
+            .value()
+            .as_str()
+            .ok_or(RenderErrorReason::InvalidParamType("string"))?;
+        Ok(ScopedJson::Derived(self.0.parse(raw)?))
+    }
+}
@@ -0,0 +1,183 @@
+use std::borrow::Cow;
+
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext,
+    RenderError, RenderErrorReason, Renderable, ScopedJson,
+};
+use serde_json::Value as Json;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+handlebars_helper!(slug: |value: str| slugify(value));
+handlebars_helper!(truncate: |value: str, width: u64| cut(value, width as usize).to_owned());
+handlebars_helper!(ellipsis: |value: str, width: u64| ellipsize(value, width as usize));
+handlebars_helper!(wordwrap: |value: str, width: u64| wrap(value, width as usize));
+handlebars_helper!(join: |values: array, separator: str| {
+    values.iter().map(text).collect::<Vec<_>>().join(separator)
+});
+handlebars_helper!(split: |value: str, separator: str| {
+    value.split(separator).map(str::to_owned).collect::<Vec<_>>()
+});
+handlebars_helper!(trim: |value: str| value.trim());
+handlebars_helper!(pad_left: |value: Json, width: u64, *args| {
+    let (value, padding) = pad(value, width as usize, args.get(2).copied());
+    format!("{}{}", padding, value)
+});
+handlebars_helper!(pad_right: |value: Json, width: u64, *args| {
+    let (value, padding) = pad(value, width as usize, args.get(2).copied());
+    format!("{}{}", value, padding)
+});
+handlebars_helper!(replace: |value: str, from: str, to: str| value.replace(from, to));
+
+/// The helper putting the given number of spaces before every line of a string, or of its block.
+pub struct Indent;
+
+impl HelperDef for Indent {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let content = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("indent", 0))?;
+        let width = width(h, 1)?;
+        let indented = indent_lines(&text(content.value()), width);
+        Ok(ScopedJson::Derived(Json::from(indented)))
+    }
+
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let indented = match h.template() {
+            // The block has already been escaped as it rendered.
+            Some(template) => indent_lines(&template.renders(r, ctx, rc)?, width(h, 0)?),
+            None => {
+                let indented = self.call_inner(h, r, ctx, rc)?.render();
+                if rc.is_disable_escape() {
+                    indented
+                } else {
+                    r.get_escape_fn()(&indented)
+                }
+            }
+        };
+        out.write(&indented)?;
+        Ok(())
+    }
+}
+
+/// The number of spaces to indent by given as the parameter at the index.
+fn width(h: &Helper, index: usize) -> Result<usize, RenderError> {
+    let width = h
+        .param(index)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex("indent", index))?;
+    let width = width
+        .value()
+        .as_u64()
+        .ok_or(RenderErrorReason::InvalidParamType("number"))?;
+    Ok(width as usize)
+}
+
+/// What is put at the end of a string shortened by `ellipsis`.
+const ELLIPSIS: char = '…';
+
+/// The string in lower case without diacritics, with every run of characters which are not letters
+/// or digits replaced by a single dash, and no dashes at either end.
+fn slugify(value: &str) -> String {
+    let mut slugged = String::with_capacity(value.len());
+    for c in value.nfd().filter(|&c| !is_combining_mark(c)) {
+        if c.is_alphanumeric() {
+            slugged.extend(c.to_lowercase());
+        } else if !slugged.is_empty() && !slugged.ends_with('-') {
+            slugged.push('-');
+        }
+    }
+    let trimmed = slugged.trim_end_matches('-').len();
+    slugged.truncate(trimmed);
+    slugged
+}
+
+/// The first characters of the string, up to the given number of them.
+fn cut(value: &str, width: usize) -> &str {
+    match value.char_indices().nth(width) {
+        Some((end, _)) => &value[..end],
+        None => value,
+    }
+}
+
+/// The string shortened to the given number of characters with an ellipsis at the end, if it is
+/// longer than that.
+fn ellipsize(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        return value.to_owned();
+    }
+    let mut shortened = cut(value, width.saturating_sub(1)).trim_end().to_owned();
+    shortened.push(ELLIPSIS);
+    shortened
+}
+
+/// The string with its lines broken between words so that none is longer than the given number of
+/// characters, unless it is a single word which is. The lines it already has are kept.
+fn wrap(value: &str, width: usize) -> String {
+    let mut wrapped = String::with_capacity(value.len());
+    for (index, line) in value.split('\n').enumerate() {
+        if index > 0 {
+            wrapped.push('\n');
+        }
+        let mut length = 0;
+        for word in line.split_whitespace() {
+            let len = word.chars().count();
+            if length > 0 && length + 1 + len > width {
+                wrapped.push('\n');
+                length = 0;
+            } else if length > 0 {
+                wrapped.push(' ');
+                length += 1;
+            }
+            wrapped.push_str(word);
+            length += len;
+        }
+    }
+    wrapped
+}
+
+/// A value as it is rendered: a string as it is, nothing for null, and anything else as JSON.
+pub(super) fn text(value: &Json) -> Cow<'_, str> {
+    match value {
+        Json::String(value) => Cow::Borrowed(value),
+        Json::Null => Cow::Borrowed(""),
+        value => Cow::Owned(value.to_string()),
+    }
+}
+
+/// A value as it is rendered along with the padding which makes it the given number of characters
+/// long, made of the given string, or spaces if there is none.
+fn pad<'a>(value: &'a Json, width: usize, padding: Option<&Json>) -> (Cow<'a, str>, String) {
+    let value = text(value);
+    let padding = padding.map_or(Cow::Borrowed(" "), text);
+    let missing = width.saturating_sub(value.chars().count());
+    let padding = padding.chars().cycle().take(missing).collect();
+    (value, padding)
+}
+
+/// The string with the given number of spaces before each of its lines which are not blank.
+fn indent_lines(value: &str, width: usize) -> String {
+    let spaces = " ".repeat(width);
+    value
+        .split_inclusive('\n')
+        .map(|line| {
+            if line.trim().is_empty() {
+                Cow::Borrowed(line)
+            } else {
+                Cow::Owned(format!("{}{}", spaces, line))
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,114 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, Renderable,
+};
+
+use super::strings::text;
+
+/// The ANSI escape code ending any styling.
+const RESET: &str = "\x1b[0m";
+
+/// The names of the colours and styles text can be given with `color`, along with the parameter
+/// of their ANSI escape code.
+const STYLES: &[(&str, &str)] = &[
+    ("bold", "1"),
+    ("dim", "2"),
+    ("italic", "3"),
+    ("underline", "4"),
+    ("black", "30"),
+    ("red", "31"),
+    ("green", "32"),
+    ("yellow", "33"),
+    ("blue", "34"),
+    ("magenta", "35"),
+    ("cyan", "36"),
+    ("white", "37"),
+    ("bright-black", "90"),
+    ("bright-red", "91"),
+    ("bright-green", "92"),
+    ("bright-yellow", "93"),
+    ("bright-blue", "94"),
+    ("bright-magenta", "95"),
+    ("bright-cyan", "96"),
+    ("bright-white", "97"),
+];
+
+/// The helper giving text, or its block, a colour or style with ANSI escape codes when colour is
+/// enabled, and leaving it as it is otherwise. The style is the one named by the helper itself, or
+/// by the parameter after the text for `color`.
+pub struct Paint {
+    /// The name of the style, or nothing for that given as a parameter.
+    pub style: Option<&'static str>,
+    pub enabled: bool,
+}
+
+impl Paint {
+    /// The name of the helper.
+    fn helper(&self) -> &'static str {
+        self.style.unwrap_or("color")
+    }
+
+    /// The parameter of the ANSI escape code for the style to give the text.
+    fn code(&self, h: &Helper) -> Result<&'static str, RenderError> {
+        let (name, index) = match self.style {
+            Some(style) => (style, None),
+            // The block form has no text before the name.
+            None => {
+                let index = if h.template().is_some() { 0 } else { 1 };
+                let name = h
+                    .param(index)
+                    .ok_or(RenderErrorReason::ParamNotFoundForIndex("color", index))?
+                    .value()
+                    .as_str()
+                    .ok_or(RenderErrorReason::InvalidParamType("string"))?;
+                (name, Some(index))
+            }
+        };
+        let code = STYLES
+            .iter()
+            .find(|(style, _)| *style == name)
+            .map(|(_, code)| *code);
+        Ok(code.ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "{} does not know the style '{}'{}",
+                self.helper(),
+                name,
+                index.map_or("", |_| ", such as red, bold or bright-blue")
+            ))
+        })?)
+    }
+}
+
+impl HelperDef for Paint {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let code = self.code(h)?;
+        let contents = match h.template() {
+            // The block has already been escaped as it rendered.
+            Some(template) => template.renders(r, ctx, rc)?,
+            None => {
+                let value = h
+                    .param(0)
+                    .ok_or(RenderErrorReason::ParamNotFoundForIndex(self.helper(), 0))?;
+                let value = text(value.value());
+                if rc.is_disable_escape() {
+                    value.into_owned()
+                } else {
+                    r.get_escape_fn()(&value)
+                }
+            }
+        };
+        if self.enabled {
+            out.write(&format!("\x1b[{}m{}{}", code, contents, RESET))?;
+        } else {
+            out.write(&contents)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeDelta};
+use handlebars::{handlebars_helper, RenderError, RenderErrorReason};
+use serde_json::Value as Json;
+
+/// The format dates are written in unless another is given, which is that of RFC 3339.
+const DEFAULT_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+
+/// The formats of dates and times without a time zone which are read, taken to be in UTC.
+const NAIVE_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+handlebars_helper!(now: |*args| {
+    let format = match args.first() {
+        Some(format) => format
+            .as_str()
+            .ok_or(RenderErrorReason::InvalidParamType("string"))?,
+        None => DEFAULT_FORMAT,
+    };
+    write_date("now", &Local::now().fixed_offset(), format)?
+});
+handlebars_helper!(date_format: |value: Json, format: str| {
+    write_date("date_format", &read_date("date_format", value)?, format)?
+});
+handlebars_helper!(date_add: |value: Json, amount: str| {
+    let date = read_date("date_add", value)?;
+    let added = date.checked_add_signed(read_duration("date_add", amount)?).ok_or_else(|| {
+        RenderErrorReason::Other(format!("date_add could not add {} to {}", amount, date))
+    })?;
+    added.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+});
+handlebars_helper!(date_parse: |value: str, format: str| {
+    parse_date(value, format)
+        .ok_or_else(|| {
+            RenderErrorReason::Other(format!(
+                "date_parse could not read '{}' as a date in the format '{}'",
+                value, format
+            ))
+        })?
+        .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+});
+
+/// Read a date given in the properties, which is either a number of seconds since the Unix epoch
+/// or a string in RFC 3339 format, such as 2024-05-01T12:00:00Z. The time and the time zone can
+/// be left out of the string, in which case the date is taken to be at midnight UTC.
+///
+/// It fails the render of the helper with the given name if the value is none of those.
+pub(super) fn read_date(helper: &str, value: &Json) -> Result<DateTime<FixedOffset>, RenderError> {
+    let parsed = match value {
+        Json::Number(seconds) => seconds
+            .as_i64()
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+            .map(|date| date.fixed_offset()),
+        Json::String(raw) => DateTime::parse_from_rfc3339(raw)
+            .ok()
+            .or_else(|| {
+                NAIVE_FORMATS
+                    .iter()
+                    .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+                    .map(|naive| naive.and_utc().fixed_offset())
+            })
+            .or_else(|| {
+                let date = NaiveDate::parse_from_str(raw, "%Y-%m-%d").ok()?;
+                Some(date.and_hms_opt(0, 0, 0)?.and_utc().fixed_offset())
+            }),
+        _ => None,
+    };
+    Ok(parsed.ok_or_else(|| {
+        RenderErrorReason::Other(format!(
+            "{} could not read the date {}, which must be a number of seconds since the Unix \
+                 epoch or a string such as 2024-05-01T12:00:00Z",
+            helper, value
+        ))
+    })?)
+}
+
+/// Parse a date in the given strftime format, with or without a time and a time zone, taking it to
+/// be in UTC without one.
+fn parse_date(value: &str, format: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_str(value, format)
+        .ok()
+        .or_else(|| {
+            let naive = NaiveDateTime::parse_from_str(value, format).ok()?;
+            Some(naive.and_utc().fixed_offset())
+        })
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(value, format).ok()?;
+            Some(date.and_hms_opt(0, 0, 0)?.and_utc().fixed_offset())
+        })
+}
+
+/// Write a date in the given strftime format, failing the render of the helper with the given name
+/// if the format is not valid.
+fn write_date(
+    helper: &str,
+    date: &DateTime<FixedOffset>,
+    format: &str,
+) -> Result<String, RenderError> {
+    let mut written = String::new();
+    write!(written, "{}", date.format(format)).map_err(|_| {
+        RenderErrorReason::Other(format!("{} format '{}' is not valid", helper, format))
+    })?;
+    Ok(written)
+}
+
+/// Read a duration such as 3d, 90m or -1h30m, made of whole numbers of weeks (w), days (d), hours
+/// (h), minutes (m) and seconds (s) and negative if it starts with -.
+///
+/// It fails the render of the helper with the given name if the duration is not valid.
+fn read_duration(helper: &str, raw: &str) -> Result<TimeDelta, RenderError> {
+    let invalid = || {
+        RenderErrorReason::Other(format!(
+            "{} could not read the duration '{}', which must be such as 3d or -1h30m",
+            helper, raw
+        ))
+    };
+    let (negative, mut rest) = match raw.trim().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, raw.trim()),
+    };
+    if rest.is_empty() {
+        return Err(invalid().into());
+    }
+
+    let mut total = TimeDelta::zero();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let amount = rest[..digits].parse::<i64>().map_err(|_| invalid())?;
+        let unit = rest[digits..].chars().next().ok_or_else(invalid)?;
+        let delta = match unit {
+            'w' => TimeDelta::try_weeks(amount),
+            'd' => TimeDelta::try_days(amount),
+            'h' => TimeDelta::try_hours(amount),
+            'm' => TimeDelta::try_minutes(amount),
+            's' => TimeDelta::try_seconds(amount),
+            _ => None,
+        };
+        total = delta
+            .and_then(|delta| total.checked_add(&delta))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+    Ok(if negative { -total } else { total })
+}
@@ -0,0 +1,68 @@
+use handlebars::{handlebars_helper, RenderError, RenderErrorReason};
+use semver::{BuildMetadata, Prerelease, Version, VersionReq};
+
+handlebars_helper!(semver_major: |version: str| parse("semver_major", version)?.major);
+handlebars_helper!(semver_minor: |version: str| parse("semver_minor", version)?.minor);
+handlebars_helper!(semver_patch: |version: str| parse("semver_patch", version)?.patch);
+handlebars_helper!(semver_bump: |version: str, part: str| {
+    bump(parse("semver_bump", version)?, part)?.to_string()
+});
+handlebars_helper!(semver_matches: |version: str, requirement: str| {
+    let parsed = VersionReq::parse(requirement).map_err(|err| {
+        RenderErrorReason::Other(format!(
+            "semver_matches could not read the requirement '{}': {}",
+            requirement, err
+        ))
+    })?;
+    parsed.matches(&parse("semver_matches", version)?)
+});
+
+/// Parse a semantic version, which may start with a v as tags often do, failing the render of the
+/// helper with the given name if it is not valid.
+fn parse(helper: &str, version: &str) -> Result<Version, RenderError> {
+    let trimmed = version.trim();
+    let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+    Ok(Version::parse(trimmed).map_err(|err| {
+        RenderErrorReason::Other(format!(
+            "{} could not read the version '{}': {}",
+            helper, version, err
+        ))
+    })?)
+}
+
+/// The next version after a version for a release of the given part, which is major, minor or
+/// patch. A pre-release is released as the version it comes before if that is the next one of
+/// that part, so that 2.0.0-rc.1 becomes 2.0.0 for major.
+fn bump(mut version: Version, part: &str) -> Result<Version, RenderError> {
+    let pre = !version.pre.is_empty();
+    match part {
+        "major" => {
+            if !(pre && version.minor == 0 && version.patch == 0) {
+                version.major = version.major.saturating_add(1);
+            }
+            version.minor = 0;
+            version.patch = 0;
+        }
+        "minor" => {
+            if !(pre && version.patch == 0) {
+                version.minor = version.minor.saturating_add(1);
+            }
+            version.patch = 0;
+        }
+        "patch" => {
+            if !pre {
+                version.patch = version.patch.saturating_add(1);
+            }
+        }
+        _ => {
+            return Err(RenderErrorReason::Other(format!(
+                "semver_bump cannot bump '{}', which must be major, minor or patch",
+                part
+            ))
+            .into())
+        }
+    }
+    version.pre = Prerelease::EMPTY;
+    version.build = BuildMetadata::EMPTY;
+    Ok(version)
+}
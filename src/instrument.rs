@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::mem;
+
+use handlebars::template::{HelperTemplate, Parameter, Template, TemplateElement, TemplateMapping};
+use handlebars::Handlebars;
+
+/// The elements to put before and after an element of a template.
+pub type Wrapping = (Option<TemplateElement>, Option<TemplateElement>);
+
+/// Rewrite every template registered so far, along with the blocks inside them, by putting the
+/// elements `wrap` returns for each element, given where it starts if that is known, around it.
+pub fn instrument(
+    handlebars: &mut Handlebars,
+    wrap: &dyn Fn(&TemplateElement, Option<&TemplateMapping>) -> Wrapping,
+) {
+    let templates = handlebars
+        .get_templates()
+        .iter()
+        .map(|(name, template)| (name.clone(), template.clone()))
+        .collect::<Vec<_>>();
+    for (name, mut template) in templates {
+        instrument_template(&mut template, wrap);
+        handlebars.register_template(&name, template);
+    }
+}
+
+fn instrument_template(
+    template: &mut Template,
+    wrap: &dyn Fn(&TemplateElement, Option<&TemplateMapping>) -> Wrapping,
+) {
+    let elements = mem::take(&mut template.elements);
+    let mapping = mem::take(&mut template.mapping);
+    // The mapping of an element to where it starts is left out for the else of a chain.
+    let mapped = mapping.len() == elements.len();
+    let mut mapping = mapping.into_iter();
+
+    for mut element in elements {
+        let position = if mapped { mapping.next() } else { None };
+        for inner in inner_templates(&mut element) {
+            instrument_template(inner, wrap);
+        }
+
+        let (before, after) = wrap(&element, position.as_ref());
+        for element in before.into_iter().chain(Some(element)).chain(after) {
+            template.elements.push(element);
+            template.mapping.extend(position.clone());
+        }
+    }
+}
+
+/// The templates of the blocks inside an element.
+pub fn inner_templates(element: &mut TemplateElement) -> Vec<&mut Template> {
+    match element {
+        TemplateElement::HelperBlock(helper) => helper
+            .template
+            .iter_mut()
+            .chain(helper.inverse.iter_mut())
+            .collect(),
+        TemplateElement::PartialBlock(decorator) | TemplateElement::DecoratorBlock(decorator) => {
+            decorator.template.iter_mut().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// An expression calling the helper with the given name and parameters, which need not be a name
+/// a template could call.
+pub fn call(helper: &str, params: Vec<Parameter>) -> TemplateElement {
+    TemplateElement::Expression(Box::new(HelperTemplate {
+        name: Parameter::Name(helper.to_owned()),
+        params,
+        hash: HashMap::new(),
+        block_param: None,
+        template: None,
+        inverse: None,
+        block: false,
+        chain: false,
+    }))
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use handlebars::template::{
+    DecoratorTemplate, HelperTemplate, Parameter, Template, TemplateElement, TemplateMapping,
+};
+use handlebars::Handlebars;
+use serde_json::Value as Json;
+
+use crate::instrument;
+
+/// The names of the blocks templates inherit from layouts with.
+const EXTEND: &str = "extend";
+const BLOCK: &str = "block";
+
+/// Rewrite every template registered so far which uses `{{#extend "layout"}}` or
+/// `{{#block "name"}}` into the partial blocks the engine understands.
+///
+/// An `{{#extend "layout"}}` becomes `{{#> layout}}`, with each `{{#block "name"}}` inside it
+/// becoming an `{{#*inline "name"}}` which overrides the block of the same name in the layout.
+/// Any other `{{#block "name"}}` is a block of a layout, and becomes `{{#> name}}`, which renders
+/// its own contents unless they are overridden.
+pub fn expand(handlebars: &mut Handlebars) {
+    let templates = handlebars
+        .get_templates()
+        .iter()
+        .map(|(name, template)| (name.clone(), template.clone()))
+        .collect::<Vec<_>>();
+    for (name, mut template) in templates {
+        if expand_template(&mut template, false) {
+            handlebars.register_template(&name, template);
+        }
+    }
+}
+
+/// Rewrite the template registered under the given name so that it extends the layout registered
+/// under the other, as though it was inside `{{#extend "layout"}}`.
+pub fn wrap(handlebars: &mut Handlebars, name: &str, layout: &str) {
+    let page = match handlebars.get_template(name) {
+        Some(page) => page.clone(),
+        None => return,
+    };
+    let extend = HelperTemplate {
+        name: Parameter::Name(EXTEND.to_owned()),
+        params: vec![Parameter::Literal(Json::from(layout))],
+        hash: HashMap::new(),
+        block_param: None,
+        template: Some(page.clone()),
+        inverse: None,
+        block: true,
+        chain: false,
+    };
+    let wrapped = Template {
+        name: page.name,
+        elements: vec![TemplateElement::HelperBlock(Box::new(extend))],
+        mapping: vec![TemplateMapping(1, 1)],
+    };
+    handlebars.register_template(name, wrapped);
+}
+
+/// Rewrite the blocks of a template inheriting from a layout, given whether it is inside
+/// `{{#extend}}`, returning whether there were any.
+fn expand_template(template: &mut Template, extending: bool) -> bool {
+    let mut expanded = false;
+    for element in &mut template.elements {
+        if let TemplateElement::HelperBlock(helper) = element {
+            let name = match helper.params.first() {
+                Some(Parameter::Literal(Json::String(name))) => Some(name.clone()),
+                _ => None,
+            };
+            match (helper.name.as_name(), name) {
+                (Some(EXTEND), Some(layout)) => {
+                    let mut inner = helper.template.take().unwrap_or_default();
+                    expand_template(&mut inner, true);
+                    *element = TemplateElement::PartialBlock(Box::new(DecoratorTemplate {
+                        name: Parameter::Name(layout),
+                        params: helper.params.drain(1..).collect(),
+                        hash: helper.hash.drain().collect(),
+                        template: Some(inner),
+                        indent: None,
+                    }));
+                    expanded = true;
+                    continue;
+                }
+                (Some(BLOCK), Some(block)) => {
+                    let mut inner = helper.template.take().unwrap_or_default();
+                    expand_template(&mut inner, false);
+                    let (name, params) = if extending {
+                        let block = Parameter::Literal(Json::from(block));
+                        (Parameter::Name("inline".to_owned()), vec![block])
+                    } else {
+                        (Parameter::Name(block), Vec::new())
+                    };
+                    let decorator = Box::new(DecoratorTemplate {
+                        name,
+                        params,
+                        hash: HashMap::new(),
+                        template: Some(inner),
+                        indent: None,
+                    });
+                    *element = if extending {
+                        TemplateElement::DecoratorBlock(decorator)
+                    } else {
+                        TemplateElement::PartialBlock(decorator)
+                    };
+                    expanded = true;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        for inner in instrument::inner_templates(element) {
+            expanded |= expand_template(inner, extending);
+        }
+    }
+    expanded
+}
@@ -1,23 +1,333 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::env;
+use std::io::{self, Read};
+use std::iter;
 use std::path::PathBuf;
 use std::process;
-use std::str::FromStr;
+use std::time::Instant;
 
-use handlebars::Handlebars;
-use serde_json::value::Value as Json;
-use snafu::{ResultExt, Snafu};
+use handlebars::{DirectorySourceOptions, Handlebars};
+use log::{debug, info, LevelFilter};
+use serde_json::value::{Map, Value as Json};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use crate::analysis::Shape;
+use crate::archive::Archive;
+use crate::cli::Action;
+use crate::config::Config;
+use crate::data::{Assignment, Format};
+use crate::deps::Tree;
+use crate::output::{Colour, Destination, Encoding, LineEndings, SplitMarker};
+use crate::profile::Profile;
+use crate::render::Escape;
+use crate::render_dir::{Rename, Symlinks};
+use crate::report::ErrorFormat;
+
+mod analysis;
+mod archive;
+mod ast;
+mod bench;
+mod cli;
+mod config;
+mod data;
+mod deps;
+mod depth;
+mod front_matter;
+mod helpers;
+mod instrument;
+mod layout;
+mod output;
+mod profile;
+mod render;
+mod render_dir;
+mod report;
+mod trace;
 
 /// An enumeration of the possible errors this program may encounter.
 #[derive(Debug, Snafu)]
-enum Error {
+pub enum Error {
+    /// This error represents a properties format being requested that is not supported.
+    #[snafu(display("Unknown properties format '{}'.", format))]
+    UnknownFormat { format: String },
+
+    /// This error represents the properties file existing but not being readable.
+    #[snafu(display("Unable to read properties from '{}': {}", path.display(), source))]
+    PropsUnreadable { source: io::Error, path: PathBuf },
+
+    /// This error represents stdin not being readable.
+    #[snafu(display("Unable to read from stdin: {}", source))]
+    StdinUnreadable { source: io::Error },
+
     /// This error represents the properties being parsed as invalid JSON.
     #[snafu(display("Unable to parse properties JSON: {}", source))]
     PropsInvalidJson { source: serde_json::Error },
 
+    /// This error represents the properties being parsed as invalid JSON5.
+    #[snafu(display("Unable to parse properties JSON5: {}", source))]
+    PropsInvalidJson5 { source: json5::Error },
+
+    /// This error represents the properties being parsed as invalid YAML.
+    #[snafu(display("Unable to parse properties YAML: {}", source))]
+    PropsInvalidYaml { source: serde_yaml::Error },
+
+    /// This error represents the properties being parsed as invalid TOML.
+    #[snafu(display(
+        "Unable to parse properties TOML at line {}, column {}: {}",
+        line,
+        column,
+        message
+    ))]
+    PropsInvalidToml {
+        #[snafu(source(from(toml::de::Error, Box::new)))]
+        source: Box<toml::de::Error>,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    /// This error represents the properties being parsed as invalid CSV.
+    #[snafu(display("Unable to parse properties CSV: {}", source))]
+    PropsInvalidCsv { source: csv::Error },
+
+    /// This error represents the properties being parsed as invalid INI.
+    #[snafu(display("Unable to parse properties INI: {}", source))]
+    PropsInvalidIni { source: ini::ParseError },
+
+    /// This error represents the properties being parsed as invalid XML.
+    #[snafu(display("Unable to parse properties XML: {}", source))]
+    PropsInvalidXml { source: quick_xml::Error },
+
+    /// This error represents the properties being parsed as invalid MessagePack.
+    #[snafu(display("Unable to parse properties MessagePack: {}", source))]
+    PropsInvalidMsgpack { source: rmp_serde::decode::Error },
+
+    /// This error represents the properties being parsed as invalid CBOR.
+    #[snafu(display("Unable to parse properties CBOR: {}", source))]
+    PropsInvalidCbor {
+        source: ciborium::de::Error<io::Error>,
+    },
+
+    /// This error represents the properties being parsed as invalid HCL.
+    #[snafu(display("Unable to parse properties HCL: {}", source))]
+    PropsInvalidHcl { source: hcl::Error },
+
+    /// This error represents properties in a text format not being valid UTF-8.
+    #[snafu(display("Properties were not valid UTF-8: {}", source))]
+    PropsNotUtf8 { source: std::str::Utf8Error },
+
+    /// This error represents the properties not being an object when other values need to be
+    /// merged into them.
+    #[snafu(display("Properties must be an object to merge other values into them."))]
+    PropsNotAnObject,
+
+    /// This error represents an assignment such as `--set path=value` missing its path or value.
+    #[snafu(display("Invalid assignment '{}', expected PATH=VALUE.", assignment))]
+    InvalidAssignment { assignment: String },
+
+    /// This error represents the value of a `--set-json` assignment not being valid JSON.
+    #[snafu(display("Unable to parse value for '{}' as JSON: {}", path, source))]
+    AssignmentInvalidJson {
+        source: serde_json::Error,
+        path: String,
+    },
+
+    /// This error represents the file of a `--set-file` assignment not being readable.
+    #[snafu(display("Unable to read value from '{}': {}", path, source))]
+    AssignmentFileUnreadable { source: io::Error, path: String },
+
+    /// This error represents a string in the properties failing to render as a template with
+    /// `--render-values`.
+    #[snafu(display("Property '{}' failed to render as a template: {}", path, source))]
+    ValueRenderFailed {
+        source: handlebars::RenderError,
+        path: String,
+    },
+
+    /// This error represents the strings in the properties still changing after as many passes as
+    /// `--render-values` allows, which is most likely because they refer to each other.
+    #[snafu(display(
+        "Property '{}' still changed when rendering the properties to a depth of {}, most likely because it refers to itself. Raise the limit with --render-values=DEPTH if it is meant to be this deep.",
+        path,
+        depth
+    ))]
+    ValuesUnsettled { path: String, depth: usize },
+
+    /// This error represents a data directory not being readable.
+    #[snafu(display("Unable to read data directory '{}': {}", path.display(), source))]
+    DataDirUnreadable { source: io::Error, path: PathBuf },
+
+    /// This error represents a dotenv file not being readable or not being valid.
+    #[snafu(display("Unable to read dotenv file '{}': {}", path.display(), source))]
+    DotenvInvalid {
+        source: dotenvy::Error,
+        path: PathBuf,
+    },
+
+    /// This error represents the directory to write the output to not being creatable.
+    #[snafu(display("Unable to create output directory '{}': {}", path.display(), source))]
+    OutputDirUncreatable { source: io::Error, path: PathBuf },
+
+    /// This error represents the path to write the output to being a template that could not be
+    /// rendered.
+    #[snafu(display("Output path '{}' failed to render: {}", path, source))]
+    OutputPathRenderFailed {
+        source: handlebars::RenderError,
+        path: String,
+    },
+
+    /// This error represents the marker given with `--split-marker` not saying where the path goes.
+    #[snafu(display(
+        "Split marker '{}' does not contain <path> where the file path goes.",
+        marker
+    ))]
+    SplitMarkerInvalid { marker: String },
+
+    /// This error represents a rule given with `--rename` missing what it replaces.
+    #[snafu(display(
+        "Invalid rename rule '{}', expected FROM=>TO such as .yaml.hbs=>.yaml.",
+        rule
+    ))]
+    RenameInvalid { rule: String },
+
+    /// This error represents the archive to write not being of a known kind.
+    #[snafu(display(
+        "Unable to tell the kind of archive '{}' from its extension, which should be one of .tar, .tar.gz, .tgz or .zip.",
+        path.display()
+    ))]
+    ArchiveFormatUnknown { path: PathBuf },
+
+    /// This error represents a tarball not being writable.
+    #[snafu(display("Unable to write archive '{}': {}", path.display(), source))]
+    ArchiveUnwritable { source: io::Error, path: PathBuf },
+
+    /// This error represents a zip archive not being writable.
+    #[snafu(display("Unable to write archive '{}': {}", path.display(), source))]
+    ArchiveZipUnwritable {
+        source: zip::result::ZipError,
+        path: PathBuf,
+    },
+
+    /// This error represents a rendered result containing a character the encoding it is to be
+    /// written in cannot encode.
+    #[snafu(display(
+        "Unable to write '{}' as {}, which cannot encode the character '{}'.",
+        path.display(),
+        encoding,
+        character
+    ))]
+    OutputNotEncodable {
+        path: PathBuf,
+        encoding: String,
+        character: char,
+    },
+
+    /// This error represents the output not being writable to the requested file.
+    #[snafu(display("Unable to write output to '{}': {}", path.display(), source))]
+    OutputUnwritable { source: io::Error, path: PathBuf },
+
+    /// This error represents a directory tree of templates not being readable.
+    #[snafu(display("Unable to read templates from '{}': {}", path.display(), source))]
+    InputDirUnreadable {
+        source: ignore::Error,
+        path: PathBuf,
+    },
+
+    /// This error represents a symlink in a directory tree of templates pointing to a directory
+    /// containing it, which would be followed without end.
+    #[snafu(display(
+        "Symlink '{}' points to '{}', which contains it. Use --symlinks preserve or --symlinks skip to not follow symlinks.",
+        path.display(),
+        ancestor.display()
+    ))]
+    SymlinkLoop { path: PathBuf, ancestor: PathBuf },
+
+    /// This error represents the path given with `--each` not being an array in the properties.
+    #[snafu(display(
+        "Expected an array at '{}' in the properties to render each element of.",
+        path
+    ))]
+    EachNotArray { path: String },
+
+    /// This error represents partials being included more deeply than `--max-include-depth`
+    /// allows, which is most likely because one of them includes itself without end.
+    #[snafu(display(
+        "Partial '{}' was included more than {} levels deep, most likely because it includes itself. Raise the limit with --max-include-depth if it is meant to be this deep.",
+        partial,
+        max
+    ))]
+    IncludeDepthExceeded { partial: String, max: usize },
+
+    /// This error represents the front matter of a template not being valid YAML.
+    #[snafu(display("Unable to parse the front matter of template '{}': {}", name, source))]
+    FrontMatterInvalid {
+        source: serde_yaml::Error,
+        name: String,
+    },
+
+    /// This error represents the front matter of a template not being a mapping of values.
+    #[snafu(display("The front matter of template '{}' must be a mapping of values.", name))]
+    FrontMatterNotAnObject { name: String },
+
+    /// This error represents a template starting with front matter when it is read from disk
+    /// again each time it is used, and so rendered with its front matter.
+    #[snafu(display(
+        "Template '{}' starts with front matter, which cannot be removed with --dev.",
+        name
+    ))]
+    DevFrontMatter { name: String },
+
+    /// This error represents some of the templates checked with `validate` not being valid.
+    #[snafu(display("{} of {} templates were not valid.", invalid, total))]
+    TemplatesInvalid { invalid: usize, total: usize },
+
+    /// This error represents stdout not being writable.
+    #[snafu(display("Unable to write to stdout: {}", source))]
+    StdoutUnwritable { source: io::Error },
+
+    /// This error represents a file to copy as it is from the input tree not being readable.
+    #[snafu(display("Unable to read file '{}' to copy: {}", path.display(), source))]
+    StaticFileUnreadable { source: io::Error, path: PathBuf },
+
+    /// This error represents the configuration file not being readable.
+    #[snafu(display("Unable to read configuration from '{}': {}", path.display(), source))]
+    ConfigUnreadable { source: io::Error, path: PathBuf },
+
+    /// This error represents the configuration file not being valid.
+    #[snafu(display("Unable to parse configuration '{}': {}", path.display(), source))]
+    ConfigInvalid {
+        #[snafu(source(from(toml::de::Error, Box::new)))]
+        source: Box<toml::de::Error>,
+        path: PathBuf,
+    },
+
+    /// This error represents an environment variable configuring this program not being valid.
+    #[snafu(display("Invalid value '{}' for environment variable {}.", value, name))]
+    EnvVarInvalid { name: String, value: String },
+
+    /// This error represents a script to register as a helper not being readable or valid.
+    #[snafu(display("Unable to register helper script '{}': {}", path.display(), message))]
+    HelperScriptInvalid { path: PathBuf, message: String },
+
     /// This error represents the Handlebars template not being found at the provided path.
     #[snafu(display("Unable to read template from '{}'.", path))]
     TemplateNotFound { path: String },
 
+    /// This error represents a template directory not being found at the provided path.
+    #[snafu(display("Unable to find template directory '{}'.", path.display()))]
+    TemplateDirNotFound { path: PathBuf },
+
+    /// This error represents a template in a template directory not being valid Handlebars syntax.
+    #[snafu(display("Directory '{}' contained an invalid handlebars template: {}", path.display(), source))]
+    TemplateDirInvalid {
+        source: handlebars::TemplateError,
+        path: PathBuf,
+    },
+
+    /// This error represents no template with the requested name being in the template directories.
+    #[snafu(display("No template named '{}' in the template directories.", name))]
+    TemplateNameNotFound { name: String },
+
     /// This error represents the Handlebars template not being valid Handlebars syntax.
     #[snafu(display("File at '{}' was not a valid handlebars template: {}", path.display(), source))]
     TemplateInvalid {
@@ -25,6 +335,16 @@ enum Error {
         path: PathBuf,
     },
 
+    /// This error represents the template given on the command line not being valid Handlebars
+    /// syntax.
+    #[snafu(display("Template string was not a valid handlebars template: {}", source))]
+    TemplateStringInvalid { source: handlebars::TemplateError },
+
+    /// This error represents the template given on the command line attempting to use properties
+    /// not provided, and so rendering failed.
+    #[snafu(display("Template string failed to render: {}", source))]
+    TemplateStringRenderFailed { source: handlebars::RenderError },
+
     /// This error represents the Handlebars template attempting to use properties not provided,
     /// and so rendering failed.
     #[snafu(display("Template at '{}' failed to render: {}", path.display(), source))]
@@ -34,70 +354,713 @@ enum Error {
     },
 }
 
-type Result<T, E = Error> = std::result::Result<T, E>;
+pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-static USAGE: &str =
-    "handlebars-cli — Template JSON properties into Handlebars templates from the CLI.
+/// The options this program was invoked with.
+struct Options {
+    format: Option<Format>,
+    dotenv: Vec<PathBuf>,
+    env_prefix: Option<String>,
+    data_files: Vec<PathBuf>,
+    data_dirs: Vec<PathBuf>,
+    assignments: Vec<Assignment>,
+    render_values: Option<usize>,
+    raw_props: Vec<String>,
+    command: Command,
+    template_dirs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    mkdir: bool,
+    no_trailing_newline: bool,
+    strip_blank_lines: bool,
+    each: Option<String>,
+    layout: Option<String>,
+    split_marker: Option<SplitMarker>,
+    archive: Option<PathBuf>,
+    copy_static: bool,
+    renames: Vec<Rename>,
+    symlinks: Symlinks,
+    strict: Option<bool>,
+    partials: Vec<(String, PathBuf)>,
+    helpers: Vec<(String, PathBuf)>,
+    line_endings: Option<LineEndings>,
+    output_encoding: Encoding,
+    no_config: bool,
+    props_given: bool,
+    default_data_files: Vec<PathBuf>,
+    output_dir: Option<PathBuf>,
+    log_level: LevelFilter,
+    error_format: ErrorFormat,
+    trace: bool,
+    profile: bool,
+    keep_missing: bool,
+    max_include_depth: usize,
+    dev: bool,
+    escape: Escape,
+    seed: Option<u64>,
+    colour: Colour,
+}
 
-USAGE:
-    handlebars-cli <JSON> <TEMPLATE>
-    handlebars-cli --help
+impl Options {
+    /// Fill in the defaults from the configuration files for anything not given on the command
+    /// line. Directories and helpers from the configuration are registered first, and its data
+    /// files are merged beneath any other properties, so that those given on the command line take
+    /// precedence.
+    fn with_config(mut self, config: Config) -> Options {
+        self.strict = self.strict.or(config.strict);
+        self.line_endings = self.line_endings.or(config.line_endings);
+        self.output_dir = self.output_dir.or(config.output_dir);
+        self.template_dirs = config
+            .partial_dirs
+            .into_iter()
+            .chain(config.template_dirs)
+            .chain(self.template_dirs)
+            .collect();
+        self.helpers = config.helpers.into_iter().chain(self.helpers).collect();
+        self.default_data_files = config
+            .data_files
+            .into_iter()
+            .chain(self.default_data_files)
+            .collect();
+        self
+    }
+}
 
-PARAMETERS:
-    JSON: A set of valid JSON to use as properties to interpolate into the provided template file.
-    TEMPLATE: A path to a valid Handlebars template.
+/// An enumeration of the things this program can be asked to do.
+enum Command {
+    /// Render a single template, which is what happens by default.
+    Render(TemplateSource),
+    /// Render every template in the input directory tree into the same place in the output
+    /// directory tree, as with `render-dir`.
+    RenderDir {
+        input: PathBuf,
+        output: Option<PathBuf>,
+    },
+    /// List the paths of the properties the template uses, as with `vars`, as JSON if asked to.
+    Vars {
+        template: TemplateSource,
+        json: bool,
+    },
+    /// Print a JSON Schema of the properties the template uses, as with `schema`.
+    Schema(TemplateSource),
+    /// Print properties with a placeholder for every value the template uses, as with
+    /// `example-data`.
+    ExampleData(TemplateSource),
+    /// Render the template the given number of times to warm up and then the given number of times
+    /// more, reporting how long those took, as with `bench`.
+    Bench {
+        template: TemplateSource,
+        warmup: u64,
+        iterations: u64,
+    },
+    /// List the helpers which can be called inside templates, as with `helpers`.
+    Helpers,
+    /// Check that the templates at these paths are valid without rendering them, as with
+    /// `validate`.
+    Validate(Vec<String>),
+    /// Print the tree of the partials the template includes, as with `deps`, as a DOT graph if asked
+    /// to.
+    Deps { template: TemplateSource, dot: bool },
+    /// Print the elements of the template as the engine parsed them, as with `ast`, as JSON if
+    /// asked to.
+    Ast {
+        template: TemplateSource,
+        json: bool,
+    },
+}
+
+/// An enumeration of the places the template to render can come from.
+enum TemplateSource {
+    /// The template is read from the file at this path, or from stdin if it is `-`.
+    File(String),
+    /// The template is given directly on the command line, as with `--template-string`.
+    Inline(String),
+    /// The template is the one with this name from the template directories, as with `--name`.
+    Named(String),
+}
 
-FLAGS:
-    --help: Prints this usage text.";
+/// The name the template given with `--template-string` is registered under.
+const INLINE_TEMPLATE_NAME: &str = "--template-string";
 
-fn main() -> () {
-    let mut args = env::args();
-    args.next(); // skip own filename
+impl TemplateSource {
+    /// The error for the template from this source failing to render.
+    fn render_failed(&self, source: handlebars::RenderError) -> Error {
+        if let Some(err) = depth::exceeded(&source) {
+            return err;
+        }
+        match self {
+            TemplateSource::File(path) | TemplateSource::Named(path) => {
+                Error::TemplateRenderFailed {
+                    source,
+                    path: PathBuf::from(path),
+                }
+            }
+            TemplateSource::Inline(_) => Error::TemplateStringRenderFailed { source },
+        }
+    }
+}
 
-    let (raw_props, raw_filename) = match (args.next(), args.next()) {
-        (Some(raw_props), Some(raw_filename)) => (raw_props, raw_filename),
-        _ => {
-            eprintln!("{}", USAGE);
-            process::exit(1);
+fn main() {
+    let mut error_format = ErrorFormat::Text;
+    let result = match cli::parse(env::args_os()).unwrap_or_else(|err| err.exit()) {
+        Action::Run(options) => {
+            error_format = options.error_format;
+            // The engine's own logs are only wanted at the most verbose level.
+            let engine_level = match options.log_level {
+                LevelFilter::Trace => LevelFilter::Trace,
+                level => level.min(LevelFilter::Warn),
+            };
+            let trace_level = if options.trace {
+                LevelFilter::Trace
+            } else {
+                options.log_level
+            };
+            env_logger::Builder::new()
+                .filter_level(engine_level)
+                .filter_module(env!("CARGO_CRATE_NAME"), options.log_level)
+                .filter_module(concat!(env!("CARGO_CRATE_NAME"), "::trace"), trace_level)
+                .format_timestamp(None)
+                .format_target(false)
+                .init();
+            let config = if options.no_config {
+                Config::from_env()
+            } else {
+                Config::load()
+            };
+            config.and_then(|config| {
+                let options = options.with_config(config);
+                if !options.props_given && options.default_data_files.is_empty() {
+                    if let Some(err) = cli::props_required(&options.command) {
+                        err.exit();
+                    }
+                }
+                let mut profile = Profile::new(options.profile);
+                let result = run(&options, &mut profile);
+                profile.report();
+                result
+            })
         }
+        Action::Completions(shell) => {
+            cli::completions(shell, &mut io::stdout());
+            Ok(())
+        }
+        Action::Man(subcommand) => {
+            cli::man(subcommand.as_deref(), &mut io::stdout()).context(StdoutUnwritableSnafu {})
+        }
+    };
+    if let Err(err) = result {
+        report::report(&err, error_format);
+        process::exit(1)
+    }
+}
+
+/// Render the template (or templates) as described by the options, and write the result to the
+/// requested file or to stdout, keeping track of where the time goes in the profile.
+fn run(options: &Options, profile: &mut Profile) -> Result<()> {
+    let output_dir = options.output_dir.clone().unwrap_or_default();
+    let mut destination = match &options.archive {
+        Some(path) => Destination::Archive(Archive::new(&output_dir.join(path))?),
+        // Rendering a directory tree always creates the directories of the output tree.
+        None => Destination::Files {
+            dir: output_dir,
+            mkdir: options.mkdir || matches!(options.command, Command::RenderDir { .. }),
+        },
     };
 
-    match execute_handlebars_templating(raw_props, raw_filename) {
-        Ok(data) => {
-            println!("{}", data)
+    match &options.command {
+        Command::Render(template) => {
+            let props = profile.time("load properties", || load_props(options))?;
+            let mut handlebars = profile.time("compile templates", || registry(options))?;
+            let props = render_values(&handlebars, props, options)?;
+
+            match &options.each {
+                Some(path) => {
+                    let items = data::get_path(&props, path)
+                        .and_then(Json::as_array)
+                        .context(EachNotArraySnafu { path })?;
+                    let (name, front_matter) = profile.time("compile templates", || {
+                        register_page(&mut handlebars, template, options)
+                    })?;
+                    instrument_templates(&mut handlebars, options, profile);
+                    let compiled = handlebars
+                        .get_template(&name)
+                        .context(TemplateNameNotFoundSnafu { name: &name })?;
+
+                    for (index, item) in items.iter().enumerate() {
+                        let item = front_matter::beneath(front_matter.as_ref(), item);
+                        let item = item.as_ref();
+                        let locals = render::each_locals(index, items.len());
+                        let start = Instant::now();
+                        let data = profile.time("render", || {
+                            render::render_with_locals(&handlebars, compiled, item, &locals)
+                        });
+                        let data = data.map_err(|source| template.render_failed(source))?;
+                        debug!(
+                            "Rendered '{}' for element {} in {:?}",
+                            name,
+                            index,
+                            start.elapsed()
+                        );
+                        let output = Output::new(&handlebars, item, &locals);
+                        profile.time("write", || {
+                            write_output(options, &mut destination, &output, &data)
+                        })?;
+                    }
+                }
+                None => {
+                    let data = execute_handlebars_templating(
+                        &mut handlebars,
+                        template,
+                        &props,
+                        options,
+                        profile,
+                    )?;
+                    let output = Output::new(&handlebars, &props, &[]);
+                    profile.time("write", || {
+                        write_output(options, &mut destination, &output, &data)
+                    })?;
+                }
+            }
+        }
+        Command::RenderDir { input, output } => {
+            let props = profile.time("load properties", || load_props(options))?;
+            let mut handlebars = profile.time("compile templates", || registry(options))?;
+            let props = render_values(&handlebars, props, options)?;
+            let output = match output {
+                Some(output) => output::render_path(&handlebars, output, &props, &[])?,
+                None => PathBuf::new(),
+            };
+            render_dir::render_dir(
+                &mut handlebars,
+                input,
+                &output,
+                &mut destination,
+                &props,
+                options,
+                profile,
+            )?;
+        }
+        Command::Bench {
+            template,
+            warmup,
+            iterations,
+        } => {
+            let props = load_props(options)?;
+            let mut handlebars = registry(options)?;
+            let props = render_values(&handlebars, props, options)?;
+            let name = register_template(&mut handlebars, template)?;
+            let front_matter = front_matter::take(&mut handlebars, &name)?;
+            let props = front_matter::beneath(front_matter.as_ref(), &props);
+            let timings = bench::bench(&handlebars, &name, &props, *warmup, *iterations)
+                .map_err(|source| template.render_failed(source))?;
+            println!("{}", timings);
+        }
+        Command::Vars { template, json } => {
+            let paths = shape(options, template)?.paths();
+            if *json {
+                println!("{}", Json::from(paths));
+            } else {
+                for path in paths {
+                    println!("{}", path);
+                }
+            }
         }
-        Err(err) => {
-            eprintln!("{}", err);
-            process::exit(1)
+        Command::Schema(template) => {
+            let schema = shape(options, template)?.schema();
+            println!("{:#}", schema);
+        }
+        Command::ExampleData(template) => {
+            let example = shape(options, template)?.example();
+            println!("{:#}", example);
+        }
+        Command::Deps { template, dot } => {
+            let mut handlebars = registry(options)?;
+            let name = register_template(&mut handlebars, template)?;
+            layout::expand(&mut handlebars);
+            let tree = Tree::of(&handlebars, &name);
+            if *dot {
+                print!("{}", tree.to_dot());
+            } else {
+                print!("{}", tree.to_text());
+            }
+        }
+        Command::Ast { template, json } => {
+            let mut handlebars = Handlebars::new();
+            let name = register_template(&mut handlebars, template)?;
+            let compiled = handlebars
+                .get_template(&name)
+                .context(TemplateNameNotFoundSnafu { name: &name })?;
+            if *json {
+                println!("{:#}", ast::to_json(compiled));
+            } else {
+                print!("{}", ast::to_text(compiled));
+            }
+        }
+        Command::Helpers => {
+            for helper in helpers::HELPERS {
+                println!("{}\n    {}", helper.signature, helper.description);
+            }
+            for (name, path) in &options.helpers {
+                println!("{{{{{} ...}}}}", name);
+                println!("    Runs the Rhai script at '{}'.", path.display());
+            }
+        }
+        Command::Validate(paths) => {
+            let mut invalid = 0usize;
+            for path in paths {
+                let template = TemplateSource::File(path.clone());
+                if let Err(err) = register_template(&mut Handlebars::new(), &template) {
+                    report::report(&err, options.error_format);
+                    invalid += 1;
+                }
+            }
+            if invalid > 0 {
+                let total = paths.len();
+                return TemplatesInvalidSnafu { invalid, total }.fail();
+            }
         }
     }
+
+    match destination {
+        Destination::Archive(archive) => archive.write(options.mkdir),
+        Destination::Files { .. } => Ok(()),
+    }
+}
+
+/// What a rendered result is written with: the registry and the properties and local variables to
+/// render the `--output` path with.
+struct Output<'a> {
+    handlebars: &'a Handlebars<'a>,
+    props: &'a Json,
+    locals: &'a [(&'a str, Json)],
 }
 
-/// Given a string which should contain valid JSON representing a set of properties, take those
-/// properties and interpolate them into a handlebars template at the given path.
+impl<'a> Output<'a> {
+    fn new(handlebars: &'a Handlebars, props: &'a Json, locals: &'a [(&'a str, Json)]) -> Self {
+        Output {
+            handlebars,
+            props,
+            locals,
+        }
+    }
+}
+
+/// Write a rendered result to the destination at the `--output` path if there is one, rendering
+/// it with the given properties and local variables first, or to stdout otherwise.
+///
+/// With `--split-marker`, each marked section is written to the destination at its own path
+/// instead, and only what comes before the first marker is written as above, unless it is blank.
+fn write_output(
+    options: &Options,
+    destination: &mut Destination,
+    output: &Output,
+    data: &str,
+) -> Result<()> {
+    let data = finish(options, data);
+    let data = match &options.split_marker {
+        Some(marker) => {
+            let (leading, sections) = marker.split(&data);
+            for (path, contents) in sections {
+                destination.write_text(&path, contents, options.output_encoding)?;
+            }
+            if leading.trim().is_empty() {
+                return Ok(());
+            }
+            leading
+        }
+        None => &data,
+    };
+
+    match &options.output {
+        Some(path) => {
+            let path = output::render_path(output.handlebars, path, output.props, output.locals)?;
+            destination.write_text(&path, data, options.output_encoding)
+        }
+        None if options.no_trailing_newline => {
+            print!("{}", data);
+            Ok(())
+        }
+        None => {
+            let newline = options.line_endings.map_or("\n", LineEndings::newline);
+            print!("{}{}", data, newline);
+            Ok(())
+        }
+    }
+}
+
+/// A rendered result with the blank lines removed if asked to with `--strip-blank-lines`, and with
+/// the requested line endings.
+fn finish<'a>(options: &Options, data: &'a str) -> Cow<'a, str> {
+    let data = if options.strip_blank_lines {
+        Cow::Owned(output::strip_blank_lines(data))
+    } else {
+        Cow::Borrowed(data)
+    };
+    match options.line_endings {
+        Some(line_endings) => Cow::Owned(line_endings.apply(&data).into_owned()),
+        None => data,
+    }
+}
+
+/// Given the properties loaded from the command line, interpolate them into the handlebars template
+/// from the given source, registering it with the registry first if it is not already registered.
 ///
 /// If everything succeeds, this will return the templated result.
 ///
-/// It fails if the properties are not valid JSON.
 /// It fails if the template file could not be found.
 /// It fails if the template file is not a valid Handlebars template.
 /// It fails if the template file used properties that were not available.
-fn execute_handlebars_templating(raw_props: String, raw_filename: String) -> Result<String, Error> {
-    let props = Json::from_str(&raw_props).context(PropsInvalidJsonSnafu {})?;
+fn execute_handlebars_templating(
+    handlebars: &mut Handlebars,
+    template: &TemplateSource,
+    props: &Json,
+    options: &Options,
+    profile: &mut Profile,
+) -> Result<String> {
+    let (name, front_matter) = profile.time("compile templates", || {
+        register_page(handlebars, template, options)
+    })?;
+    let props = front_matter::beneath(front_matter.as_ref(), props);
+    instrument_templates(handlebars, options, profile);
+    let start = Instant::now();
+    let rendered = profile.time("render", || handlebars.render(&name, &props));
+    let rendered = rendered.map_err(|source| template.render_failed(source))?;
+    debug!("Rendered '{}' in {:?}", name, start.elapsed());
+    Ok(rendered)
+}
 
-    let filename = PathBuf::from(&raw_filename);
-    if !filename.exists() {
-        return TemplateNotFoundSnafu { path: raw_filename }.fail();
+/// Rewrite the templates registered so far to inherit from the layouts they extend, to log what
+/// happens as they render, if asked to with
+/// `--trace`, to fail once partials are included more deeply than `--max-include-depth` allows,
+/// and to time the partials they expand, if asked to with `--profile`.
+fn instrument_templates(handlebars: &mut Handlebars, options: &Options, profile: &Profile) {
+    layout::expand(handlebars);
+    if options.trace {
+        trace::instrument(handlebars, &helper_names(options));
     }
+    depth::limit(handlebars, options.max_include_depth);
+    profile.instrument(handlebars);
+}
 
+/// The names of the helpers this program makes available and of those registered from scripts,
+/// which can be called without arguments like properties are used.
+fn helper_names(options: &Options) -> BTreeSet<String> {
+    helpers::HELPERS
+        .iter()
+        .map(|helper| helper.name.to_owned())
+        .chain(options.helpers.iter().map(|(name, _)| name.clone()))
+        .collect()
+}
+
+/// Register the template from the given source as with `register_template`, along with the layout
+/// given with `--layout` if there is one, which the template then extends, returning its name and
+/// the values of any front matter they start with, those of the template over those of the layout.
+///
+/// It fails if either template could not be registered.
+/// It fails if either template starts with front matter which is not valid.
+/// It fails if either template starts with front matter when `--dev` is given.
+fn register_page(
+    handlebars: &mut Handlebars,
+    template: &TemplateSource,
+    options: &Options,
+) -> Result<(String, Option<Json>)> {
+    let name = register_template(handlebars, template)?;
+    let mut front_matter = front_matter::take(handlebars, &name)?;
+    if options.dev && front_matter.is_some() {
+        return DevFrontMatterSnafu { name }.fail();
+    }
+    if let Some(layout) = &options.layout {
+        let layout = register_template(handlebars, &TemplateSource::File(layout.clone()))?;
+        if let Some(mut values) = front_matter::take(handlebars, &layout)? {
+            if let Some(page) = front_matter.take() {
+                data::deep_merge(&mut values, page);
+            }
+            front_matter = Some(values);
+        }
+        layout::wrap(handlebars, &name, &layout);
+    }
+    Ok((name, front_matter))
+}
+
+/// Register the template from the given source with the registry if it is not already registered,
+/// returning the name it is registered under.
+///
+/// It fails if the template file could not be found.
+/// It fails if the template file is not a valid Handlebars template.
+fn register_template(handlebars: &mut Handlebars, template: &TemplateSource) -> Result<String> {
+    match template {
+        TemplateSource::File(raw_filename) => {
+            let filename = PathBuf::from(raw_filename);
+            if raw_filename == "-" {
+                let mut template = String::new();
+                io::stdin()
+                    .read_to_string(&mut template)
+                    .context(StdinUnreadableSnafu {})?;
+                handlebars
+                    .register_template_string(raw_filename, template)
+                    .context(TemplateInvalidSnafu { path: &filename })?;
+            } else {
+                if !filename.exists() {
+                    return TemplateNotFoundSnafu { path: raw_filename }.fail();
+                }
+                handlebars
+                    .register_template_file(raw_filename, &filename)
+                    .context(TemplateInvalidSnafu { path: &filename })?;
+            }
+            info!("Registered template '{}'", raw_filename);
+            Ok(raw_filename.clone())
+        }
+        TemplateSource::Inline(template) => {
+            handlebars
+                .register_template_string(INLINE_TEMPLATE_NAME, template)
+                .context(TemplateStringInvalidSnafu {})?;
+            Ok(INLINE_TEMPLATE_NAME.to_owned())
+        }
+        TemplateSource::Named(name) => {
+            if !handlebars.has_template(name) {
+                return TemplateNameNotFoundSnafu { name }.fail();
+            }
+            Ok(name.clone())
+        }
+    }
+}
+
+/// The shape of the properties the template from the given source uses, along with the partials it
+/// includes from the template directories.
+///
+/// It fails if the template could not be registered.
+fn shape(options: &Options, template: &TemplateSource) -> Result<Shape> {
+    let mut handlebars = registry(options)?;
+    let name = register_template(&mut handlebars, template)?;
+    layout::expand(&mut handlebars);
+    Ok(Shape::of(&handlebars, &name, &helper_names(options)))
+}
+
+/// Given strings which should contain valid JSON (or the requested format) representing sets of
+/// properties, the paths of files containing them (or given with `--data-file`) or `-` for stdin,
+/// deep merge those properties along with all of the other properties given in the options into
+/// the rendering context.
+///
+/// It fails if the properties are not valid in their format.
+/// It fails if a dotenv file could not be read.
+fn load_props(options: &Options) -> Result<Json> {
+    let start = Instant::now();
+    let mut props = Json::Object(Map::new());
+    for path in &options.default_data_files {
+        data::deep_merge(&mut props, data::load_props_file(path, options.format)?);
+    }
+    for path in &options.data_dirs {
+        data::deep_merge(&mut props, data::load_props_dir(path, options.format)?);
+    }
+    for raw_props in &options.raw_props {
+        data::deep_merge(&mut props, data::load_props(raw_props, options.format)?);
+    }
+    for path in &options.data_files {
+        data::deep_merge(&mut props, data::load_props_file(path, options.format)?);
+    }
+    for path in &options.dotenv {
+        data::merge_top_level(&mut props, data::load_dotenv(path)?)?;
+    }
+    if let Some(prefix) = &options.env_prefix {
+        let env = Json::Object(data::environment(prefix));
+        data::merge_top_level(&mut props, iter::once(("env".to_owned(), env)).collect())?;
+    }
+    for assignment in &options.assignments {
+        assignment.apply(&mut props)?;
+    }
+
+    debug!("Loaded properties in {:?}", start.elapsed());
+    Ok(props)
+}
+
+/// The properties with the strings in them which are templates rendered, if asked to with
+/// `--render-values`.
+///
+/// It fails if a string could not be rendered or the strings did not stop changing.
+fn render_values(handlebars: &Handlebars, mut props: Json, options: &Options) -> Result<Json> {
+    if let Some(depth) = options.render_values {
+        render::render_values(handlebars, &mut props, depth)?;
+    }
+    Ok(props)
+}
+
+/// Create the Handlebars registry used for rendering, configured as described by the options and
+/// with the templates of any template directories registered.
+///
+/// It fails if a template directory could not be found or contains an invalid template.
+/// It fails if a partial could not be found or is not a valid template.
+/// It fails if a helper script could not be read or is not valid.
+fn registry(options: &Options) -> Result<Handlebars<'static>> {
     let mut handlebars = Handlebars::new();
-    handlebars.set_strict_mode(true);
+    // The sources of templates are only kept to read them again if this is set first.
+    handlebars.set_dev_mode(options.dev);
+    if options.keep_missing {
+        handlebars.register_helper("helperMissing", Box::new(render::keep_missing));
+    } else {
+        handlebars.set_strict_mode(options.strict.unwrap_or(true));
+    }
+    options.escape.register(&mut handlebars);
+    let to_stdout = matches!(options.command, Command::Render(_))
+        && options.output.is_none()
+        && options.split_marker.is_none()
+        && options.archive.is_none();
+    helpers::register(
+        &mut handlebars,
+        options.seed,
+        options.colour.enabled(to_stdout),
+    );
+
+    for dir in &options.template_dirs {
+        if !dir.is_dir() {
+            return TemplateDirNotFoundSnafu { path: dir }.fail();
+        }
+        let earlier = handlebars.get_templates().clone();
+        handlebars
+            .register_templates_directory(dir, DirectorySourceOptions::default())
+            .context(TemplateDirInvalidSnafu { path: dir })?;
+        // Templates at the same relative path as those of an earlier directory take their place.
+        let overridden = earlier
+            .iter()
+            .filter(|(name, template)| handlebars.get_template(name) != Some(*template))
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        for name in &overridden {
+            debug!(
+                "Template '{}' from '{}' overrides the one registered before",
+                name,
+                dir.display()
+            );
+        }
+        info!(
+            "Registered {} templates from '{}'",
+            handlebars.get_templates().len() - earlier.len() + overridden.len(),
+            dir.display()
+        );
+    }
+    for (name, path) in &options.partials {
+        if !path.is_file() {
+            return TemplateNotFoundSnafu {
+                path: path.display().to_string(),
+            }
+            .fail();
+        }
+        handlebars
+            .register_template_file(name, path)
+            .context(TemplateInvalidSnafu { path })?;
+        info!("Registered partial '{}' from '{}'", name, path.display());
+    }
+    for name in handlebars.get_templates().keys() {
+        debug!("Template '{}' is available as a partial", name);
+    }
 
-    handlebars
-        .register_template_file(&raw_filename, &filename)
-        .context(TemplateInvalidSnafu { path: &filename })?;
+    for (name, path) in &options.helpers {
+        info!("Registering helper '{}' from '{}'", name, path.display());
+        handlebars
+            .register_script_helper_file(name, path)
+            .map_err(|err| Error::HelperScriptInvalid {
+                path: path.clone(),
+                message: err.to_string(),
+            })?;
+    }
 
-    handlebars
-        .render(&raw_filename, &props)
-        .context(TemplateRenderFailedSnafu { path: &filename })
+    Ok(handlebars)
 }
@@ -0,0 +1,367 @@
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use handlebars::{Handlebars, RenderError, RenderErrorReason, Template};
+use log::{debug, info};
+use serde::Deserialize;
+use serde_json::value::Value as Json;
+use snafu::{OptionExt, ResultExt};
+use tempfile::{Builder, NamedTempFile};
+
+use crate::archive::Archive;
+use crate::render;
+use crate::{
+    Error, OutputDirUncreatableSnafu, OutputPathRenderFailedSnafu, OutputUnwritableSnafu, Result,
+    SplitMarkerInvalidSnafu,
+};
+
+/// An enumeration of the line endings the rendered results can be converted to, whatever the line
+/// endings of the templates are.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndings {
+    Lf,
+    Crlf,
+    /// The line endings of the platform this program was built for.
+    Native,
+}
+
+impl LineEndings {
+    /// Convert every line ending in a rendered result to these line endings.
+    pub fn apply(self, data: &str) -> Cow<'_, str> {
+        let lf = data.replace("\r\n", "\n");
+        if self.is_crlf() {
+            Cow::Owned(lf.replace('\n', "\r\n"))
+        } else if lf.len() == data.len() {
+            Cow::Borrowed(data)
+        } else {
+            Cow::Owned(lf)
+        }
+    }
+
+    /// The line ending itself, as written after the result on stdout.
+    pub fn newline(self) -> &'static str {
+        if self.is_crlf() {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    fn is_crlf(self) -> bool {
+        match self {
+            LineEndings::Lf => false,
+            LineEndings::Crlf => true,
+            LineEndings::Native => cfg!(windows),
+        }
+    }
+}
+
+/// An enumeration of when the styling helpers colour their text with ANSI escape codes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Colour {
+    /// When the result is written to stdout, stdout is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Colour {
+    /// Whether the text is to be coloured, given whether the result is written to stdout rather
+    /// than to files.
+    pub fn enabled(self, to_stdout: bool) -> bool {
+        match self {
+            Colour::Auto => {
+                to_stdout && io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+            }
+            Colour::Always => true,
+            Colour::Never => false,
+        }
+    }
+}
+
+/// An enumeration of the encodings the rendered files can be written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 with a byte order mark, which some Windows tools need to tell it is UTF-8.
+    Utf8Bom,
+    /// UTF-16 little-endian with a byte order mark, as Windows tools expect.
+    Utf16le,
+    /// ISO-8859-1, which can only encode the first 256 characters of Unicode.
+    Latin1,
+}
+
+impl Encoding {
+    /// Encode a rendered result in this encoding, or return the first character it cannot encode.
+    pub fn encode(self, data: &str) -> std::result::Result<Vec<u8>, char> {
+        match self {
+            Encoding::Utf8 => Ok(data.as_bytes().to_vec()),
+            Encoding::Utf8Bom => Ok(b"\xEF\xBB\xBF"
+                .iter()
+                .chain(data.as_bytes())
+                .copied()
+                .collect()),
+            Encoding::Utf16le => Ok(iter::once(0xFEFF)
+                .chain(data.encode_utf16())
+                .flat_map(u16::to_le_bytes)
+                .collect()),
+            Encoding::Latin1 => data
+                .chars()
+                .map(|c| {
+                    if u32::from(c) < 256 {
+                        Ok(c as u8)
+                    } else {
+                        Err(c)
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// The name of this encoding as given on the command line.
+    fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// A rendered result without the lines which are empty or only whitespace, keeping the line endings
+/// of the others.
+pub fn strip_blank_lines(data: &str) -> String {
+    data.split_inclusive('\n')
+        .filter(|line| !line.trim().is_empty())
+        .collect()
+}
+
+/// The placeholder in a split marker for where the path of the file goes.
+const SPLIT_MARKER_PATH: &str = "<path>";
+
+/// A marker for the lines which split a rendered result into sections for several files, given
+/// with `--split-marker` such as `--- file: <path>`.
+#[derive(Clone, Debug)]
+pub struct SplitMarker {
+    prefix: String,
+    suffix: String,
+}
+
+impl SplitMarker {
+    /// Parse a split marker from the command line, which must contain `<path>` where the path of
+    /// each file goes.
+    pub fn parse(raw: &str) -> Result<SplitMarker> {
+        let (prefix, suffix) = raw
+            .split_once(SPLIT_MARKER_PATH)
+            .context(SplitMarkerInvalidSnafu { marker: raw })?;
+        Ok(SplitMarker {
+            prefix: prefix.to_owned(),
+            suffix: suffix.to_owned(),
+        })
+    }
+
+    /// Split a rendered result on the lines matching this marker into what comes before the first
+    /// marker, and the path and contents of each marked section in order.
+    pub fn split<'a>(&self, data: &'a str) -> (&'a str, Vec<(PathBuf, &'a str)>) {
+        let mut sections = Vec::new();
+        let mut current: Option<(PathBuf, usize)> = None;
+        let mut leading = data;
+
+        let mut offset = 0;
+        for line in data.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+
+            let path = match self.path(line) {
+                Some(path) => path,
+                None => continue,
+            };
+            match current.take() {
+                Some((previous, body)) => sections.push((previous, &data[body..start])),
+                None => leading = &data[..start],
+            }
+            current = Some((path, offset));
+        }
+        if let Some((path, body)) = current {
+            sections.push((path, &data[body..]));
+        }
+
+        (leading, sections)
+    }
+
+    /// The path given by a line if it matches this marker.
+    fn path(&self, line: &str) -> Option<PathBuf> {
+        let path = line
+            .trim_end_matches(&['\r', '\n'][..])
+            .strip_prefix(&self.prefix)?
+            .strip_suffix(&self.suffix)?
+            .trim();
+        if path.is_empty() {
+            return None;
+        }
+        Some(PathBuf::from(path))
+    }
+}
+
+/// Render a path to write output to, which may itself be a Handlebars template such as
+/// `out/{{service.name}}.conf`, with the given properties and local variables.
+///
+/// It fails if the path is a template that fails to render.
+pub fn render_path(
+    handlebars: &Handlebars,
+    path: &Path,
+    props: &Json,
+    locals: &[(&str, Json)],
+) -> Result<PathBuf> {
+    let raw = path.to_string_lossy();
+    if !raw.contains("{{") {
+        return Ok(path.to_owned());
+    }
+
+    Template::compile(&raw)
+        .map_err(|err| RenderError::from(RenderErrorReason::TemplateError(err)))
+        .and_then(|template| render::render_with_locals(handlebars, &template, props, locals))
+        .map(PathBuf::from)
+        .context(OutputPathRenderFailedSnafu { path: raw })
+}
+
+/// An enumeration of the places the rendered files are written to.
+pub enum Destination {
+    /// Each file is written where its path says, relative to the given directory, creating its
+    /// parent directories first if asked to.
+    Files { dir: PathBuf, mkdir: bool },
+    /// Each file is added to an archive instead, as with `--archive`.
+    Archive(Archive),
+}
+
+impl Destination {
+    /// Write a rendered result to this destination as a file in the given encoding.
+    ///
+    /// It fails if the result contains characters the encoding cannot encode.
+    /// It fails if the file could not be written.
+    pub fn write_text(&mut self, path: &Path, contents: &str, encoding: Encoding) -> Result<()> {
+        let contents =
+            encoding
+                .encode(contents)
+                .map_err(|character| Error::OutputNotEncodable {
+                    path: path.to_owned(),
+                    encoding: encoding.name(),
+                    character,
+                })?;
+        self.write(path, &contents)
+    }
+
+    /// Write a symlink pointing to the given target to this destination, replacing any file already
+    /// there.
+    ///
+    /// It fails if the symlink could not be created.
+    pub fn link(&mut self, path: &Path, target: &Path) -> Result<()> {
+        match self {
+            Destination::Files { dir, mkdir } => {
+                let path = dir.join(path);
+                write_link(&path, target, *mkdir)?;
+                info!("Linked '{}' to '{}'", path.display(), target.display());
+                Ok(())
+            }
+            Destination::Archive(archive) => {
+                archive.add_link(path, target);
+                debug!(
+                    "Added '{}' linking to '{}' to the archive",
+                    path.display(),
+                    target.display()
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Write a file to this destination.
+    ///
+    /// It fails if the file could not be written.
+    pub fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        match self {
+            Destination::Files { dir, mkdir } => {
+                let path = dir.join(path);
+                write_file(&path, contents, *mkdir)?;
+                info!("Wrote '{}'", path.display());
+                Ok(())
+            }
+            Destination::Archive(archive) => {
+                archive.add(path, contents);
+                debug!("Added '{}' to the archive", path.display());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Write the rendered result to the file at the given path, creating its parent directories first
+/// if asked to.
+///
+/// The result is written to a temporary file next to the destination which is then moved into
+/// place, so the destination is never left partially written.
+///
+/// It fails if the parent directories could not be created.
+/// It fails if the file could not be written.
+pub fn write_file(path: &Path, contents: &[u8], mkdir: bool) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if mkdir {
+        fs::create_dir_all(dir).context(OutputDirUncreatableSnafu { path: dir })?;
+    }
+
+    let mut file = temporary_file(path, dir).context(OutputUnwritableSnafu { path })?;
+    file.write_all(contents)
+        .context(OutputUnwritableSnafu { path })?;
+    file.persist(path)
+        .map_err(|err| err.error)
+        .context(OutputUnwritableSnafu { path })?;
+
+    Ok(())
+}
+
+/// Create a symlink at the given path pointing to the target, replacing any file already there and
+/// creating its parent directories first if asked to.
+///
+/// It fails if the parent directories could not be created.
+/// It fails if the symlink could not be created.
+fn write_link(path: &Path, target: &Path, mkdir: bool) -> Result<()> {
+    if let Some(dir) = path
+        .parent()
+        .filter(|dir| mkdir && !dir.as_os_str().is_empty())
+    {
+        fs::create_dir_all(dir).context(OutputDirUncreatableSnafu { path: dir })?;
+    }
+    if fs::symlink_metadata(path).is_ok() {
+        fs::remove_file(path).context(OutputUnwritableSnafu { path })?;
+    }
+    #[cfg(unix)]
+    let linked = std::os::unix::fs::symlink(target, path);
+    #[cfg(windows)]
+    let linked = std::os::windows::fs::symlink_file(target, path);
+    linked.context(OutputUnwritableSnafu { path })
+}
+
+/// Create a temporary file in the given directory to be moved to the given path, with the
+/// permissions of any existing file at that path, or the usual ones for a new file otherwise.
+fn temporary_file(path: &Path, dir: &Path) -> io::Result<NamedTempFile> {
+    let mut builder = Builder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let permissions = fs::metadata(path)
+            .map(|metadata| metadata.permissions())
+            .unwrap_or_else(|_| fs::Permissions::from_mode(0o644));
+        builder.permissions(permissions);
+    }
+    builder.tempfile_in(dir)
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use handlebars::template::{Parameter, TemplateElement};
+use handlebars::{Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext};
+use serde_json::Value as Json;
+
+use crate::instrument;
+
+/// The names of the helpers timing partials, which no template can call.
+const PARTIAL_START_HELPER: &str = "--profile-partial-start";
+const PARTIAL_END_HELPER: &str = "--profile-partial-end";
+
+/// The time spent in each stage of a run, each template rendered and each partial expanded, which
+/// is reported with `--profile`.
+pub struct Profile {
+    enabled: bool,
+    stages: Vec<(&'static str, Duration)>,
+    templates: Vec<(String, Duration)>,
+    partials: Arc<Mutex<Partials>>,
+}
+
+/// The partials being expanded, innermost last, with when they started, and the number of times
+/// each partial was expanded along with the total time it took.
+#[derive(Default)]
+struct Partials {
+    running: Vec<(String, Instant)>,
+    totals: BTreeMap<String, (usize, Duration)>,
+}
+
+impl Profile {
+    /// A profile which only keeps track of anything if it is enabled.
+    pub fn new(enabled: bool) -> Profile {
+        Profile {
+            enabled,
+            stages: Vec::new(),
+            templates: Vec::new(),
+            partials: Arc::default(),
+        }
+    }
+
+    /// Run a stage, adding the time it took to the time spent in stages of the same name.
+    pub fn time<T>(&mut self, stage: &'static str, run: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = run();
+        let elapsed = start.elapsed();
+        match self.stages.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += elapsed,
+            None => self.stages.push((stage, elapsed)),
+        }
+        result
+    }
+
+    /// Record the time a template took to render, when rendering several.
+    pub fn template(&mut self, name: &str, elapsed: Duration) {
+        if self.enabled {
+            self.templates.push((name.to_owned(), elapsed));
+        }
+    }
+
+    /// Rewrite every template registered so far to time each partial it expands, if enabled.
+    pub fn instrument(&self, handlebars: &mut Handlebars) {
+        if !self.enabled {
+            return;
+        }
+        let timer = |start| PartialTimer {
+            partials: Arc::clone(&self.partials),
+            start,
+        };
+        handlebars.register_helper(PARTIAL_START_HELPER, Box::new(timer(true)));
+        handlebars.register_helper(PARTIAL_END_HELPER, Box::new(timer(false)));
+        instrument::instrument(handlebars, &|element, _| match element {
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                let name = partial.name.as_name().unwrap_or_default();
+                let name = vec![Parameter::Literal(Json::from(name))];
+                (
+                    Some(instrument::call(PARTIAL_START_HELPER, name)),
+                    Some(instrument::call(PARTIAL_END_HELPER, Vec::new())),
+                )
+            }
+            _ => (None, None),
+        });
+    }
+
+    /// Print the report of where the time went to stderr, if enabled.
+    pub fn report(&self) {
+        if !self.enabled {
+            return;
+        }
+        let partials = self.partials.lock().unwrap_or_else(|err| err.into_inner());
+        let width = self
+            .stages
+            .iter()
+            .map(|(name, _)| name.len())
+            .chain(self.templates.iter().map(|(name, _)| name.len()))
+            .chain(partials.totals.keys().map(String::len))
+            .max()
+            .unwrap_or_default();
+
+        eprintln!("Stages:");
+        for (name, elapsed) in &self.stages {
+            eprintln!("  {:<width$}  {:?}", name, elapsed, width = width);
+        }
+        if !self.templates.is_empty() {
+            eprintln!("Templates:");
+            for (name, elapsed) in &self.templates {
+                eprintln!("  {:<width$}  {:?}", name, elapsed, width = width);
+            }
+        }
+        if !partials.totals.is_empty() {
+            eprintln!("Partials:");
+            for (name, (count, elapsed)) in &partials.totals {
+                eprintln!(
+                    "  {:<width$}  {:?} over {} expansions",
+                    name,
+                    elapsed,
+                    count,
+                    width = width
+                );
+            }
+        }
+    }
+}
+
+/// The helper put before a partial to note when it starts, or after it to add the time it took to
+/// the total for the partial.
+struct PartialTimer {
+    partials: Arc<Mutex<Partials>>,
+    start: bool,
+}
+
+impl HelperDef for PartialTimer {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        let mut partials = self.partials.lock().unwrap_or_else(|err| err.into_inner());
+        if self.start {
+            let name = h.param(0).and_then(|param| param.value().as_str());
+            let name = name.unwrap_or_default().to_owned();
+            partials.running.push((name, Instant::now()));
+        } else if let Some((name, start)) = partials.running.pop() {
+            let total = partials.totals.entry(name).or_default();
+            total.0 += 1;
+            total.1 += start.elapsed();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use clap::ValueEnum;
+use handlebars::{
+    BlockContext, Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, Renderable, StringOutput, Template,
+};
+use serde_json::value::Value as Json;
+use snafu::ResultExt;
+
+use crate::{Result, ValueRenderFailedSnafu, ValuesUnsettledSnafu};
+
+/// Render a template with the given data as the root context, and with the given local variables
+/// available at the root as `@name`, as though the template was the body of an `{{#each}}`.
+pub fn render_with_locals(
+    handlebars: &Handlebars,
+    template: &Template,
+    data: &Json,
+    locals: &[(&str, Json)],
+) -> Result<String, RenderError> {
+    let ctx = Context::wraps(data)?;
+    let mut rc = RenderContext::new(template.name.as_ref());
+
+    let mut block = BlockContext::new();
+    for (name, value) in locals {
+        block.set_local_var(name, value.clone());
+    }
+    rc.push_block(block);
+
+    let mut output = StringOutput::new();
+    template.render(handlebars, &ctx, &mut rc, &mut output)?;
+    output.into_string().map_err(RenderError::from)
+}
+
+/// Render every string in the properties which contains `{{`, such as
+/// `"url": "https://{{host}}:{{port}}"`, as a template with the properties themselves as the
+/// context, without escaping anything, until none of them change.
+///
+/// Each pass renders the strings with the properties as they were after the pass before, so a
+/// string referring to another which is itself a template takes one more pass. Up to `depth` passes
+/// are made.
+///
+/// It fails if a string is not a valid template or fails to render.
+/// It fails if the strings still change after the last pass, most likely because they refer to
+/// each other.
+pub fn render_values(handlebars: &Handlebars, props: &mut Json, depth: usize) -> Result<()> {
+    let mut handlebars = handlebars.clone();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    for pass in 0.. {
+        let context = props.clone();
+        match render_strings(&handlebars, &context, props, "")? {
+            None => break,
+            Some(path) if pass == depth => return ValuesUnsettledSnafu { path, depth }.fail(),
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Render every string inside the value which contains `{{` with the context, returning the dotted
+/// path of the first which changed, if any did.
+fn render_strings(
+    handlebars: &Handlebars,
+    context: &Json,
+    value: &mut Json,
+    path: &str,
+) -> Result<Option<String>> {
+    let child = |key: &dyn std::fmt::Display| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    let mut changed = None;
+    match value {
+        Json::String(raw) if raw.contains("{{") => {
+            let rendered = handlebars
+                .render_template(raw, context)
+                .context(ValueRenderFailedSnafu { path })?;
+            if rendered != *raw {
+                *raw = rendered;
+                changed = Some(path.to_owned());
+            }
+        }
+        Json::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let item = render_strings(handlebars, context, item, &child(&index))?;
+                changed = changed.or(item);
+            }
+        }
+        Json::Object(entries) => {
+            for (key, entry) in entries.iter_mut() {
+                let entry = render_strings(handlebars, context, entry, &child(key))?;
+                changed = changed.or(entry);
+            }
+        }
+        _ => {}
+    }
+    Ok(changed)
+}
+
+/// The local variables of the element at the given index of an array of the given length, the same
+/// as those `{{#each}}` provides.
+pub fn each_locals(index: usize, len: usize) -> Vec<(&'static str, Json)> {
+    vec![
+        ("index", Json::from(index)),
+        ("first", Json::from(index == 0)),
+        ("last", Json::from(index + 1 == len)),
+    ]
+}
+
+/// Write an expression such as `{{name}}` whose property is not there as it is, so that it is left
+/// for a later tool to fill in, when registered as the helper called for anything missing.
+///
+/// It fails as the engine would for helpers which are not there, such as `{{name value}}`.
+pub fn keep_missing(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    rc: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    if !h.params().is_empty() || !h.hash().is_empty() {
+        return Err(RenderErrorReason::HelperNotFound(h.name().to_owned()).into());
+    }
+    if rc.is_disable_escape() {
+        out.write(&format!("{{{{{{{}}}}}}}", h.name()))?;
+    } else {
+        out.write(&format!("{{{{{}}}}}", h.name()))?;
+    }
+    Ok(())
+}
+
+/// An enumeration of the ways values rendered with `{{name}}` can be escaped, for the format of
+/// the result they are embedded in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Escape {
+    /// Replaces the characters special to HTML with entities, as the engine does by default.
+    #[default]
+    Html,
+    /// Replaces the characters special to XML with entities, including ' as &apos;.
+    Xml,
+    /// Escapes the value to be embedded inside a JSON string, without the quotes around it.
+    Json,
+    /// Quotes the value in single quotes to be a single word in a POSIX shell.
+    Shell,
+    /// Renders the value as it is.
+    None,
+}
+
+impl Escape {
+    /// Register the function which escapes values this way with the registry.
+    pub fn register(self, handlebars: &mut Handlebars) {
+        match self {
+            Escape::Html => handlebars.register_escape_fn(handlebars::html_escape),
+            Escape::Xml => handlebars.register_escape_fn(xml_escape),
+            Escape::Json => handlebars.register_escape_fn(json_escape),
+            Escape::Shell => handlebars.register_escape_fn(shell_escape),
+            Escape::None => handlebars.register_escape_fn(handlebars::no_escape),
+        }
+    }
+}
+
+/// The text with the characters special to XML replaced with entities, including ' as &apos;.
+pub fn xml_escape(data: &str) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for c in data.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn json_escape(data: &str) -> String {
+    let quoted = Json::from(data).to_string();
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+/// The text in single quotes for a POSIX shell, with each ' inside it ending the quotes for a \'.
+pub(crate) fn shell_escape(data: &str) -> String {
+    format!("'{}'", data.replace('\'', r"'\''"))
+}
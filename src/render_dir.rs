@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use clap::ValueEnum;
+use handlebars::Handlebars;
+use ignore::WalkBuilder;
+use log::debug;
+use serde_json::value::Value as Json;
+use snafu::{OptionExt, ResultExt};
+
+use crate::data;
+use crate::depth;
+use crate::front_matter;
+use crate::output::Destination;
+use crate::profile::Profile;
+use crate::Options;
+use crate::{
+    DevFrontMatterSnafu, Error, RenameInvalidSnafu, Result, StaticFileUnreadableSnafu,
+    TemplateDirInvalidSnafu, TemplateDirNotFoundSnafu,
+};
+
+/// An enumeration of the ways symlinks in the input tree are handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Symlinks {
+    /// Symlinks are rendered or copied as the files and directories they point to, failing if one
+    /// points to a directory containing it.
+    #[default]
+    Follow,
+    /// Symlinks are recreated in the output tree pointing to the same place, or to what it is
+    /// rendered to for a template, although the templates they point to can still be used as
+    /// partials.
+    Preserve,
+    /// Symlinks are left out as though they were not there.
+    Skip,
+}
+
+/// The extension of the files in the input tree that are rendered as templates.
+const TEMPLATE_EXTENSION: &str = ".hbs";
+
+/// The extensions of the data files next to templates which are merged over the properties for
+/// those templates alone, in the order they are merged.
+const SIDECAR_EXTENSIONS: &[&str] = &["json", "json5", "yaml", "yml", "toml"];
+
+/// The name of the files in the input tree listing, in gitignore syntax, the files and directories
+/// beneath them which are neither rendered nor copied.
+const IGNORE_FILE: &str = ".hbsignore";
+
+/// What separates the end of a template's name from what it is replaced with in a rename rule.
+const RENAME_SEPARATOR: &str = "=>";
+
+/// A rule for the name of the file a template is rendered to, given with `--rename` such as
+/// `.yaml.hbs=>.yaml`, which replaces the end of the template's name.
+#[derive(Clone, Debug)]
+pub struct Rename {
+    from: String,
+    to: String,
+}
+
+impl Rename {
+    /// Parse a rename rule from the command line, which must say what the end of the name it
+    /// replaces is.
+    pub fn parse(raw: &str) -> Result<Rename> {
+        let (from, to) = raw
+            .split_once(RENAME_SEPARATOR)
+            .filter(|(from, _)| !from.is_empty())
+            .context(RenameInvalidSnafu { rule: raw })?;
+        Ok(Rename {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        })
+    }
+}
+
+/// The name of the file a template is rendered to, given by the rule replacing the longest end of
+/// its name, or its name without the template extension if no rule does.
+fn output_name(file_name: &str, renames: &[Rename]) -> String {
+    let rule = renames
+        .iter()
+        .filter(|rename| file_name.ends_with(&rename.from))
+        .max_by_key(|rename| rename.from.len());
+    match rule {
+        Some(rename) => format!(
+            "{}{}",
+            &file_name[..file_name.len() - rename.from.len()],
+            rename.to
+        ),
+        None => file_name
+            .strip_suffix(TEMPLATE_EXTENSION)
+            .unwrap_or(file_name)
+            .to_owned(),
+    }
+}
+
+/// Render every template in the input directory tree with the given properties, writing each
+/// result to the destination at the same relative path in the output directory tree without the
+/// template extension, or with the end of its name replaced as the `--rename` rules say.
+///
+/// All of the templates in the input tree are registered before rendering, under their relative
+/// paths without the extension, so that they can be used as partials by each other. The values of
+/// the front matter a template starts with are merged beneath the properties for that template, and
+/// those of a data file next to it with the same name, such as page.json for page.hbs, over them.
+///
+/// Any other files, apart from those data files, are copied as they are to the same relative path
+/// if asked to, and are skipped otherwise.
+///
+/// Files and directories matching the patterns of a .hbsignore file in the input tree are skipped
+/// altogether, although the templates among them are still registered so that they can be used as
+/// partials.
+///
+/// Symlinks are followed, preserved or skipped as `--symlinks` says.
+///
+/// The time spent rendering each template and writing its result is kept track of in the profile.
+///
+/// It fails if the input tree could not be read or contains an invalid template.
+/// It fails if a symlink to follow points to a directory containing it.
+/// It fails if a template starts with front matter which is not valid.
+/// It fails if the data file next to a template could not be read or is not valid.
+/// It fails if a file to copy could not be read.
+/// It fails if any template failed to render.
+/// It fails if any result could not be written.
+pub fn render_dir(
+    handlebars: &mut Handlebars,
+    input: &Path,
+    output: &Path,
+    destination: &mut Destination,
+    props: &Json,
+    options: &Options,
+    profile: &mut Profile,
+) -> Result<()> {
+    if !input.is_dir() {
+        return TemplateDirNotFoundSnafu { path: input }.fail();
+    }
+    profile.time("compile templates", || {
+        register_tree(handlebars, input, options.symlinks)
+    })?;
+    let names = handlebars
+        .get_templates()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut front_matters = HashMap::new();
+    for name in names {
+        if let Some(values) = front_matter::take(handlebars, &name)? {
+            if options.dev {
+                return DevFrontMatterSnafu { name }.fail();
+            }
+            front_matters.insert(name, values);
+        }
+    }
+    crate::instrument_templates(handlebars, options, profile);
+
+    let walk = WalkBuilder::new(input)
+        .standard_filters(false)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .follow_links(options.symlinks == Symlinks::Follow)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    for entry in walk {
+        let entry = entry.map_err(|err| unreadable(err, input))?;
+        let relative = entry.path().strip_prefix(input).unwrap_or(entry.path());
+        let file_name = entry.file_name().to_string_lossy();
+        if options.symlinks == Symlinks::Preserve && entry.depth() > 0 && entry.path_is_symlink() {
+            let target = fs::read_link(entry.path())
+                .context(StaticFileUnreadableSnafu { path: entry.path() })?;
+            let path = output
+                .join(relative)
+                .with_file_name(output_name(&file_name, &options.renames));
+            let target = match target.file_name().map(|name| name.to_string_lossy()) {
+                Some(name) => target.with_file_name(output_name(&name, &options.renames)),
+                None => target,
+            };
+            destination.link(&path, &target)?;
+            continue;
+        }
+
+        let is_file = entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file());
+        if !is_file || entry.file_name() == IGNORE_FILE {
+            continue;
+        }
+
+        let stripped = match file_name.strip_suffix(TEMPLATE_EXTENSION) {
+            Some(stripped) => stripped,
+            None if options.copy_static && !is_sidecar(entry.path()) => {
+                let contents = fs::read(entry.path())
+                    .context(StaticFileUnreadableSnafu { path: entry.path() })?;
+                destination.write(&output.join(relative), &contents)?;
+                continue;
+            }
+            None => continue,
+        };
+        let name = template_name(&relative.with_file_name(stripped));
+        // Hidden and temporary files are not registered, and so are not rendered either.
+        if !handlebars.has_template(&name) {
+            debug!(
+                "Skipping '{}', which is not a template",
+                entry.path().display()
+            );
+            continue;
+        }
+
+        let mut props = front_matter::beneath(front_matters.get(&name), props);
+        for path in sidecars(entry.path()) {
+            data::deep_merge(props.to_mut(), data::load_props_file(&path, None)?);
+        }
+        let start = Instant::now();
+        let rendered = profile
+            .time("render", || handlebars.render(&name, &props))
+            .map_err(|source| match depth::exceeded(&source) {
+                Some(err) => err,
+                None => Error::TemplateRenderFailed {
+                    source,
+                    path: entry.path().to_owned(),
+                },
+            })?;
+        debug!("Rendered '{}' in {:?}", name, start.elapsed());
+        profile.template(&name, start.elapsed());
+        let path = output
+            .join(relative)
+            .with_file_name(output_name(&file_name, &options.renames));
+        let rendered = crate::finish(options, &rendered);
+        profile.time("write", || {
+            destination.write_text(&path, &rendered, options.output_encoding)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Register every template in the input tree under its relative path without the template
+/// extension, leaving out hidden and temporary files as the engine does.
+///
+/// Symlinks are followed unless they are to be skipped, so that the templates they point to can be
+/// used as partials even when the symlinks themselves are preserved.
+///
+/// It fails if the input tree could not be read or contains an invalid template.
+/// It fails if a symlink to follow points to a directory containing it.
+fn register_tree(handlebars: &mut Handlebars, input: &Path, symlinks: Symlinks) -> Result<()> {
+    let walk = WalkBuilder::new(input)
+        .standard_filters(false)
+        .follow_links(symlinks != Symlinks::Skip)
+        .build();
+    for entry in walk {
+        let entry = entry.map_err(|err| unreadable(err, input))?;
+        let is_file = entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file());
+        let file_name = entry.file_name().to_string_lossy();
+        let stripped = match file_name.strip_suffix(TEMPLATE_EXTENSION) {
+            Some(stripped) if is_file => stripped,
+            _ => continue,
+        };
+        if stripped.is_empty() || stripped.starts_with('.') || stripped.starts_with('#') {
+            continue;
+        }
+
+        let relative = entry.path().strip_prefix(input).unwrap_or(entry.path());
+        let name = template_name(&relative.with_file_name(stripped));
+        handlebars
+            .register_template_file(&name, entry.path())
+            .context(TemplateDirInvalidSnafu { path: input })?;
+        debug!("Registered template '{}'", name);
+    }
+    Ok(())
+}
+
+/// The error for the input tree not being readable, which says which symlink it was if one points
+/// to a directory containing it.
+fn unreadable(err: ignore::Error, input: &Path) -> Error {
+    match symlink_loop(&err) {
+        Some((path, ancestor)) => Error::SymlinkLoop {
+            path: path.to_owned(),
+            ancestor: ancestor.to_owned(),
+        },
+        None => Error::InputDirUnreadable {
+            source: err,
+            path: input.to_owned(),
+        },
+    }
+}
+
+/// The symlink and the directory containing it that it points to, if that is why the input tree
+/// could not be read.
+fn symlink_loop(err: &ignore::Error) -> Option<(&Path, &Path)> {
+    match err {
+        ignore::Error::Loop { ancestor, child } => Some((child, ancestor)),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => symlink_loop(err),
+        ignore::Error::Partial(errs) => errs.iter().find_map(symlink_loop),
+        _ => None,
+    }
+}
+
+/// The paths of the data files next to a template, such as page.json for page.hbs, which exist.
+fn sidecars(template: &Path) -> Vec<PathBuf> {
+    SIDECAR_EXTENSIONS
+        .iter()
+        .map(|extension| template.with_extension(extension))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Whether a file is the data file of a template next to it with the same name.
+fn is_sidecar(path: &Path) -> bool {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    extension.is_some_and(|extension| SIDECAR_EXTENSIONS.contains(&extension))
+        && path.with_extension(&TEMPLATE_EXTENSION[1..]).is_file()
+}
+
+/// The name of a template in the registry for its path relative to the input tree, which always
+/// uses `/` to separate the components.
+fn template_name(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,215 @@
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde_json::{json, Value as Json};
+
+use crate::Error;
+
+/// An enumeration of the ways an error can be reported on stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ErrorFormat {
+    /// The message of the error, for people.
+    #[default]
+    Text,
+    /// An object with the kind, message, path, line and column of the error on a single line,
+    /// for programs.
+    Json,
+}
+
+/// Print the error to stderr in the given format.
+///
+/// An error in a template file is shown as text with the offending line of the template, in colour
+/// when stderr is a terminal and `NO_COLOR` is not set.
+pub fn report(err: &Error, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => match excerpt(err).and_then(|excerpt| excerpt.render()) {
+            Some(diagnostic) => eprint!("{}", diagnostic),
+            None => eprintln!("{}", err),
+        },
+        ErrorFormat::Json => eprintln!("{}", to_json(err)),
+    }
+}
+
+/// An error at a position in a template file, which can be shown with the line it is on.
+struct Excerpt<'a> {
+    summary: String,
+    path: &'a Path,
+    line: usize,
+    column: usize,
+}
+
+/// The error as an excerpt, if it is about a position in a template file.
+///
+/// Errors rendering a partial are left out, as the position is in the partial rather than the file.
+fn excerpt(err: &Error) -> Option<Excerpt<'_>> {
+    match err {
+        Error::TemplateInvalid { source, path } => {
+            let (line, column) = source.pos()?;
+            Some(Excerpt {
+                summary: format!("invalid template: {}", source.reason()),
+                path,
+                line,
+                column,
+            })
+        }
+        Error::TemplateRenderFailed { source, path } => {
+            let in_partial = source
+                .template_name
+                .as_ref()
+                .is_some_and(|name| Path::new(name) != path.as_path());
+            if in_partial {
+                return None;
+            }
+            Some(Excerpt {
+                summary: format!("template failed to render: {}", source.reason()),
+                path,
+                line: source.line_no?,
+                column: source.column_no?,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl Excerpt<'_> {
+    /// The error with its path and position, followed by the offending line of the template with
+    /// a caret beneath the expression at fault.
+    ///
+    /// Nothing is returned if the template could no longer be read.
+    fn render(&self) -> Option<String> {
+        let template = fs::read_to_string(self.path).ok()?;
+        let source = template
+            .lines()
+            .nth(self.line.saturating_sub(1))
+            .unwrap_or_default();
+        let colour = io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+        let paint = |text: &str, style: &str| {
+            if colour {
+                format!("\x1b[{}m{}\x1b[0m", style, text)
+            } else {
+                text.to_owned()
+            }
+        };
+
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let indent = source
+            .chars()
+            .take(self.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let carets = "^".repeat(expression_width(source, self.column));
+        Some(format!(
+            "{} {}\n{}{} {}:{}:{}\n{} {}\n{} {} {}\n{} {} {}{}\n",
+            paint("error:", "1;31"),
+            paint(&self.summary, "1"),
+            gutter,
+            paint("-->", "1;34"),
+            self.path.display(),
+            self.line,
+            self.column,
+            gutter,
+            paint("|", "1;34"),
+            paint(&number, "1;34"),
+            paint("|", "1;34"),
+            source,
+            gutter,
+            paint("|", "1;34"),
+            indent,
+            paint(&carets, "1;31"),
+        ))
+    }
+}
+
+/// The width in characters of the expression starting at the one-based column of the line, which
+/// is the whole of a `{{...}}` or a single character otherwise.
+fn expression_width(line: &str, column: usize) -> usize {
+    let rest = line
+        .chars()
+        .skip(column.saturating_sub(1))
+        .collect::<String>();
+    match rest.strip_prefix("{{").and_then(|inner| inner.find("}}")) {
+        Some(end) => rest[..end + 4].chars().count(),
+        None => 1,
+    }
+}
+
+/// The error as an object of its kind, message, and the path, line and column it is about, which
+/// are null when they are not known.
+fn to_json(err: &Error) -> Json {
+    let (line, column) = position(err).unzip();
+    json!({
+        "kind": kind(err),
+        "message": err.to_string(),
+        "path": path(err),
+        "line": line,
+        "column": column,
+    })
+}
+
+/// The name of the variant of the error, which is what its debug representation starts with.
+fn kind(err: &Error) -> String {
+    let debug = format!("{:?}", err);
+    debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// The path of the file or directory the error is about, if it is about one.
+fn path(err: &Error) -> Option<String> {
+    let display = |path: &Path| Some(path.display().to_string());
+    match err {
+        Error::PropsUnreadable { path, .. }
+        | Error::DataDirUnreadable { path, .. }
+        | Error::DotenvInvalid { path, .. }
+        | Error::OutputDirUncreatable { path, .. }
+        | Error::ArchiveFormatUnknown { path }
+        | Error::ArchiveUnwritable { path, .. }
+        | Error::ArchiveZipUnwritable { path, .. }
+        | Error::OutputNotEncodable { path, .. }
+        | Error::OutputUnwritable { path, .. }
+        | Error::InputDirUnreadable { path, .. }
+        | Error::StaticFileUnreadable { path, .. }
+        | Error::ConfigUnreadable { path, .. }
+        | Error::ConfigInvalid { path, .. }
+        | Error::HelperScriptInvalid { path, .. }
+        | Error::TemplateDirNotFound { path }
+        | Error::TemplateDirInvalid { path, .. }
+        | Error::TemplateInvalid { path, .. }
+        | Error::TemplateRenderFailed { path, .. } => display(path),
+        Error::AssignmentFileUnreadable { path, .. }
+        | Error::OutputPathRenderFailed { path, .. }
+        | Error::TemplateNotFound { path } => Some(path.clone()),
+        _ => None,
+    }
+}
+
+/// The one-based line and column the error is at in the file it is about, if they are known.
+fn position(err: &Error) -> Option<(usize, usize)> {
+    match err {
+        Error::PropsInvalidJson { source } | Error::AssignmentInvalidJson { source, .. } => {
+            Some((source.line(), source.column()))
+        }
+        Error::PropsInvalidJson5 {
+            source: json5::Error::Message { location, .. },
+        } => location
+            .as_ref()
+            .map(|location| (location.line, location.column)),
+        Error::PropsInvalidYaml { source } => source
+            .location()
+            .map(|location| (location.line(), location.column())),
+        Error::PropsInvalidToml { line, column, .. } => Some((*line, *column)),
+        Error::TemplateDirInvalid { source, .. }
+        | Error::TemplateInvalid { source, .. }
+        | Error::TemplateStringInvalid { source } => source.pos(),
+        Error::OutputPathRenderFailed { source, .. }
+        | Error::TemplateStringRenderFailed { source }
+        | Error::TemplateRenderFailed { source, .. } => source.line_no.zip(source.column_no),
+        _ => None,
+    }
+}
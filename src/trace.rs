@@ -0,0 +1,109 @@
+use std::collections::BTreeSet;
+
+use handlebars::template::{Parameter, TemplateElement, TemplateMapping};
+use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext};
+use log::trace;
+use serde_json::Value as Json;
+
+use crate::instrument;
+
+/// The name the helper logging what happens is registered under, which no template can call.
+const TRACE_HELPER: &str = "--trace";
+
+/// Rewrite every template registered so far so that, as it renders, it logs each helper it calls,
+/// each partial it expands and each property it looks up, along with whether it was found.
+///
+/// Names used without arguments which are in `helpers` are taken to be helpers rather than
+/// properties.
+pub fn instrument(handlebars: &mut Handlebars, helpers: &BTreeSet<String>) {
+    handlebars.register_helper(TRACE_HELPER, Box::new(trace_helper));
+    instrument::instrument(handlebars, &|element, position| {
+        (trace_call(element, position, helpers), None)
+    });
+}
+
+/// The call to the trace helper for an element, if it does anything worth tracing.
+fn trace_call(
+    element: &TemplateElement,
+    position: Option<&TemplateMapping>,
+    helpers: &BTreeSet<String>,
+) -> Option<TemplateElement> {
+    match element {
+        TemplateElement::Expression(helper) | TemplateElement::HtmlExpression(helper) => {
+            let name = helper.name.as_name().unwrap_or_default();
+            let is_value = helper.params.is_empty()
+                && helper.hash.is_empty()
+                && matches!(helper.name, Parameter::Path(_))
+                && !helpers.contains(name);
+            if is_value {
+                Some(call("lookup", name, position, vec![helper.name.clone()]))
+            } else {
+                Some(call("helper", name, position, helper.params.clone()))
+            }
+        }
+        TemplateElement::HelperBlock(helper) => {
+            let name = helper.name.as_name().unwrap_or_default();
+            Some(call("helper", name, position, helper.params.clone()))
+        }
+        TemplateElement::PartialExpression(partial) | TemplateElement::PartialBlock(partial) => {
+            let name = partial.name.as_name().unwrap_or_default();
+            Some(call("partial", name, position, partial.params.clone()))
+        }
+        TemplateElement::RawString(_)
+        | TemplateElement::Comment(_)
+        | TemplateElement::DecoratorExpression(_)
+        | TemplateElement::DecoratorBlock(_) => None,
+    }
+}
+
+/// A call to the trace helper with what is happening, where and the values it is happening with.
+fn call(
+    kind: &str,
+    name: &str,
+    position: Option<&TemplateMapping>,
+    values: Vec<Parameter>,
+) -> TemplateElement {
+    let position = match position {
+        Some(TemplateMapping(line, column)) => format!("{}:{} ", line, column),
+        None => String::new(),
+    };
+    let mut params = vec![
+        Parameter::Literal(Json::from(kind)),
+        Parameter::Literal(Json::from(name)),
+        Parameter::Literal(Json::from(position)),
+    ];
+    params.extend(values);
+    instrument::call(TRACE_HELPER, params)
+}
+
+/// Log what is happening, and whether each of the values it is happening with was found.
+fn trace_helper(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    _: &mut dyn Output,
+) -> HelperResult {
+    let text = |index| {
+        h.param(index)
+            .and_then(|param| param.value().as_str())
+            .unwrap_or_default()
+    };
+    let (kind, name, position) = (text(0), text(1), text(2));
+    match kind {
+        "helper" => trace!("{}calling helper '{}'", position, name),
+        "partial" => trace!("{}expanding partial '{}'", position, name),
+        _ => {}
+    }
+    for param in h.params().iter().skip(3) {
+        if let Some(path) = param.relative_path() {
+            let found = if param.is_value_missing() {
+                "missed"
+            } else {
+                "hit"
+            };
+            trace!("{}looking up '{}': {}", position, path, found);
+        }
+    }
+    Ok(())
+}